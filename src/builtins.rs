@@ -0,0 +1,59 @@
+use crate::parser::ast::Type;
+
+// Funzioni di libreria (input da stdin, memoria sullo heap e stringhe) che un programma sigma chiama
+// senza dichiararle. Non hanno un corpo sigma: le implementano gli helper del runtime (codegen::riscv)
+pub struct Builtin {
+    pub helper: &'static str,   // label dell'helper da chiamare
+    pub parameters: Vec<Type>,
+    pub return_type: Type,
+    pub usage: &'static str     // come si usa, per i messaggi di errore
+}
+
+pub fn builtin(name: &str) -> Option<Builtin> {
+    match name {
+        // un intero, saltando spazi e a capo prima del numero; 0 se non c'è
+        "readBased" => Some(Builtin {
+            helper: "read_int",
+            parameters: vec![],
+            return_type: Type::Based,
+            usage: "readBased() reads an integer from stdin"
+        }),
+        // un byte, 0 a fine input
+        "readChad" => Some(Builtin {
+            helper: "read_char",
+            parameters: vec![],
+            return_type: Type::Chad,
+            usage: "readChad() reads one character from stdin"
+        }),
+        // una riga senza '\n' dentro a un array di chad, ritornata come vibes
+        "readVibes" => Some(Builtin {
+            helper: "read_line",
+            parameters: vec![Type::Array(Box::new(Type::Chad), None), Type::Based],
+            return_type: Type::Vibes,
+            usage: "readVibes(buffer, size) reads a line into a chad array of at least size elements"
+        }),
+        // size byte sullo heap, non inizializzati; ghosted se size <= 0 o se la memoria è finita.
+        // Ritorna un puntatore generico, che si assegna a qualsiasi gyatt
+        "alloc" => Some(Builtin {
+            helper: "heap_alloc",
+            parameters: vec![Type::Based],
+            return_type: Type::Pointer(Box::new(Type::Ghost)),
+            usage: "alloc(size) returns a pointer to size bytes on the heap, e.g. 'gyatt based v slay alloc(n * 4);'"
+        }),
+        // restituisce un blocco di alloc (free(ghosted) non fa niente)
+        "free" => Some(Builtin {
+            helper: "heap_free",
+            parameters: vec![Type::Pointer(Box::new(Type::Ghost))],
+            return_type: Type::Ghost,
+            usage: "free(p) releases a pointer returned by alloc"
+        }),
+        // numero di chad prima dello '\0' finale
+        "len" => Some(Builtin {
+            helper: "str_len",
+            parameters: vec![Type::Vibes],
+            return_type: Type::Based,
+            usage: "len(s) returns the number of characters in a vibes"
+        }),
+        _ => None
+    }
+}
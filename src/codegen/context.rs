@@ -1,58 +1,135 @@
 use std::collections::HashMap;
-use crate::parser::ast::Type;
+use crate::parser::ast::{DeclId, Type};
+use crate::codegen::layout::{self, StructLayout};
+
+// Dove saltano ohio (break) e fax (continue) dentro a un loop
+pub struct LoopLabels {
+    pub break_label: String,
+    // inizio del while, oppure l'incremento del for
+    pub continue_label: String,
+}
 
 pub struct CodeGenContext {
 
-    // mi serve a mappare un nome di variabile con l'offset dallo stackpointer
-    pub variables: HashMap<String, i32>,
+    // mi serve a mappare una dichiarazione (l'id che le ha dato il semantic) con l'offset dallo stackpointer
+    pub variables: HashMap<DeclId, i32>,
 
-    // mi serve per tracciare il tipo di ogni variabile
-    pub variable_types: HashMap<String, Type>,
+    // label di globali e costanti (stanno in .data/.rodata/.bss), per id della dichiarazione:
+    // a differenza delle variabili valgono in tutte le funzioni
+    pub globals: HashMap<DeclId, String>,
 
     // mi serve per tenere traccia dell'offset corrente dello stack (dovrebbe essere negativo e che sale)
     pub stack_offset: i32,
 
+    // byte occupati dalle variabili nel punto più profondo della funzione (li calcola layout_frame)
+    pub locals_size: i32,
+
+    // zona dei temporanei, subito sopra le variabili: i valori intermedi delle espressioni
+    // stanno in slot fissi del frame invece di spostare sp
+    pub temp_base: i32,
+    pub temp_offset: i32,
+    pub temp_size: i32,  // massimo raggiunto da temp_offset, serve al prologo
+
+    // stack_offset all'apertura di ogni blocco ancora aperto: quando il blocco si chiude
+    // i suoi slot tornano liberi e il blocco dopo li riusa
+    pub scope_offsets: Vec<i32>,
+
     // salvo le stringhe letterali da salvare in .data
-    pub string_literals: Vec<String>,
+    pub string_literals: Vec<Vec<u8>>,
 
     // contatore per le label uniche
     pub label_counter: usize,
 
-    // stack di label di fine loop per gestire break
-    pub loop_stack: Vec<String>,
+    // stack dei loop aperti, con le label per break e continue
+    pub loop_stack: Vec<LoopLabels>,
+
+    // label dell'epilogo della funzione corrente, dove salta yeet
+    pub return_label: String,
+
+    // tipo di ritorno della funzione corrente (un based ritornato da una funzione superBased va esteso)
+    pub return_type: Type,
+
+    // layout in memoria degli struct skibidi
+    pub structs: HashMap<String, StructLayout>,
 }
 
 impl CodeGenContext {
     pub fn new() -> Self {
         Self {
             variables: HashMap::new(),
-            variable_types: HashMap::new(),
+            globals: HashMap::new(),
             stack_offset: 0,
+            locals_size: 0,
+            temp_base: 0,
+            temp_offset: 0,
+            temp_size: 0,
+            scope_offsets: Vec::new(),
             string_literals: Vec::new(),
             label_counter: 0,
             loop_stack: Vec::new(),
+            return_label: String::new(),
+            return_type: Type::Ghost,
+            structs: HashMap::new(),
         }
     }
     
+    // byte occupati in memoria da un valore di questo tipo
+    pub fn type_size(&self, var_type: &Type) -> i32 {
+        layout::size_of(&self.structs, var_type)
+    }
+
+    pub fn type_align(&self, var_type: &Type) -> i32 {
+        layout::align_of(&self.structs, var_type)
+    }
+
+    // campo di uno struct: ritorna offset e tipo
+    pub fn field(&self, struct_name: &str, field: &str) -> Option<(i32, Type)> {
+        self.structs.get(struct_name)?
+            .fields.iter()
+            .find(|f| f.name == field)
+            .map(|f| (f.offset, f.field_type.clone()))
+    }
+
     // funzione creata per allocare una variabile sullo stack
-    // Ritorna offset POSITIVO dall'inizio del frame (dopo il prologo)
-    pub fn allocate_variable(&mut self, name: String, var_type: Type) -> i32 {
-        let offset = self.stack_offset;
-        self.stack_offset += 4; // incremento 4 che è la grandezza di una word in riscv32
-        self.variables.insert(name.clone(), offset);
-        self.variable_types.insert(name, var_type);
+    // Ritorna offset POSITIVO dall'inizio del frame (dopo il prologo), allineato al tipo
+    pub fn allocate_variable(&mut self, decl: DeclId, var_type: Type) -> i32 {
+        let offset = layout::align_to(self.stack_offset, self.type_align(&var_type));
+        self.stack_offset = offset + self.type_size(&var_type);
+        self.locals_size = self.locals_size.max(self.stack_offset);
+        self.variables.insert(decl, offset);
         offset
     }
 
-    pub fn get_variable_offset(&mut self, name: &str) -> Option<i32> {
-        self.variables.get(name).copied()
+    pub fn get_variable_offset(&self, decl: DeclId) -> Option<i32> {
+        self.variables.get(&decl).copied()
+    }
+    
+    // Prende uno slot temporaneo di `size` byte e ritorna il suo offset da s0
+    pub fn push_temp(&mut self, size: i32) -> i32 {
+        let offset = self.temp_base + self.temp_offset;
+        self.temp_offset += size;
+        self.temp_size = self.temp_size.max(self.temp_offset);
+        offset
     }
 
-    pub fn get_variable_type(&self, name: &str) -> Option<&Type> {
-        self.variable_types.get(name)
+    // Libera gli ultimi `size` byte di temporanei e ritorna l'offset da cui iniziavano
+    pub fn pop_temp(&mut self, size: i32) -> i32 {
+        self.temp_offset -= size;
+        self.temp_base + self.temp_offset
     }
-    
-    pub fn add_string_literal(&mut self, s: String) -> String {
+
+    // Blocchi di ong/nah/mewing/sixSeven: le variabili dichiarate dentro muoiono alla chiusura
+    pub fn enter_scope(&mut self) {
+        self.scope_offsets.push(self.stack_offset);
+    }
+
+    pub fn exit_scope(&mut self) {
+        if let Some(offset) = self.scope_offsets.pop() {
+            self.stack_offset = offset;
+        }
+    }
+
+    pub fn add_string_literal(&mut self, s: Vec<u8>) -> String {
         let label = format!("str_{}", self.string_literals.len());
         self.string_literals.push(s);
         label
@@ -64,9 +141,9 @@ impl CodeGenContext {
         label
     }
 
-    // Gestione loop per break (implementati da me porcodio)
-    pub fn enter_loop(&mut self, end_label: String) {
-        self.loop_stack.push(end_label);
+    // Gestione loop per break e continue (implementati da me porcodio)
+    pub fn enter_loop(&mut self, end_label: String, continue_label: String) {
+        self.loop_stack.push(LoopLabels { break_label: end_label, continue_label });
     }
 
     pub fn exit_loop(&mut self) {
@@ -74,6 +151,10 @@ impl CodeGenContext {
     }
 
     pub fn current_loop_end(&self) -> Option<&String> {
-        self.loop_stack.last()
+        self.loop_stack.last().map(|l| &l.break_label)
+    }
+
+    pub fn current_loop_continue(&self) -> Option<&String> {
+        self.loop_stack.last().map(|l| &l.continue_label)
     }
 }
\ No newline at end of file
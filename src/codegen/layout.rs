@@ -0,0 +1,95 @@
+use std::collections::HashMap;
+use crate::parser::ast::{StructDef, Type};
+
+// Disposizione in memoria di uno struct skibidi
+#[derive(Debug, Clone)]
+pub struct StructLayout {
+    pub fields: Vec<FieldLayout>,
+    pub size: i32,   // byte totali, padding finale compreso
+    pub align: i32,  // allineamento del campo più esigente
+}
+
+#[derive(Debug, Clone)]
+pub struct FieldLayout {
+    pub name: String,
+    pub field_type: Type,
+    pub offset: i32,  // distanza dall'inizio dello struct
+}
+
+// Arrotonda offset al primo multiplo di align
+pub fn align_to(offset: i32, align: i32) -> i32 {
+    (offset + align - 1) / align * align
+}
+
+// Byte occupati in memoria da un valore di questo tipo
+pub fn size_of(structs: &HashMap<String, StructLayout>, tipo: &Type) -> i32 {
+    match tipo {
+        Type::Chad | Type::NoCap => 1,
+        // superBased sta in due word: la bassa all'offset, l'alta a offset + 4
+        Type::SuperBased => 8,
+        Type::Array(elem, Some(size)) => size_of(structs, elem) * *size as i32,
+        Type::Struct(name) => structs.get(name).map_or(0, |layout| layout.size),
+        // based, chill, puntatori gyatt, stringhe e parametri array: una word di riscv32
+        _ => 4,
+    }
+}
+
+pub fn align_of(structs: &HashMap<String, StructLayout>, tipo: &Type) -> i32 {
+    match tipo {
+        Type::Chad | Type::NoCap => 1,
+        Type::SuperBased => 8,
+        Type::Array(elem, Some(_)) => align_of(structs, elem),
+        Type::Struct(name) => structs.get(name).map_or(1, |layout| layout.align),
+        _ => 4,
+    }
+}
+
+// Calcola il layout di tutti gli struct come farebbe un compilatore C:
+// i campi in ordine di dichiarazione, ognuno allineato al suo tipo,
+// e la dimensione totale arrotondata all'allineamento dello struct
+pub fn compute_layouts(defs: &[StructDef]) -> HashMap<String, StructLayout> {
+    let mut layouts = HashMap::new();
+    for def in defs {
+        compute_layout(defs, &def.name, &mut layouts);
+    }
+    layouts
+}
+
+fn compute_layout(defs: &[StructDef], name: &str, layouts: &mut HashMap<String, StructLayout>) {
+    if layouts.contains_key(name) {
+        return;
+    }
+    let Some(def) = defs.iter().find(|d| d.name == name) else {
+        return;
+    };
+
+    // prima servono i layout degli struct usati nei campi (i cicli li ha già esclusi il semantic)
+    for field in &def.fields {
+        if let Some(inner) = inner_struct(&field.field_type) {
+            compute_layout(defs, inner, layouts);
+        }
+    }
+
+    let mut fields = Vec::new();
+    let mut offset = 0;
+    let mut align = 1;
+
+    for field in &def.fields {
+        let field_align = align_of(layouts, &field.field_type);
+        offset = align_to(offset, field_align);
+        fields.push(FieldLayout { name: field.name.clone(), field_type: field.field_type.clone(), offset });
+        offset += size_of(layouts, &field.field_type);
+        align = align.max(field_align);
+    }
+
+    layouts.insert(name.to_string(), StructLayout { fields, size: align_to(offset, align), align });
+}
+
+// Nome dello struct contenuto in un campo (anche dentro un array di struct)
+fn inner_struct(tipo: &Type) -> Option<&str> {
+    match tipo {
+        Type::Struct(name) => Some(name),
+        Type::Array(elem, _) => inner_struct(elem),
+        _ => None,
+    }
+}
@@ -1,5 +1,5 @@
 pub mod context;
+pub mod layout;
 pub mod riscv;
 
-pub use context::*;
-pub use riscv::*;
\ No newline at end of file
+pub use riscv::generate_riscv;
\ No newline at end of file
@@ -1,13 +1,23 @@
+use crate::builtins::builtin;
+use crate::error::Diagnostic;
+use crate::lexer::Span;
 use crate::parser::ast::*;
 use crate::codegen::context::*;
+use crate::codegen::layout::{self, compute_layouts};
 
-pub fn generate_riscv(ast: &Program) -> Result<String, String> {
+pub fn generate_riscv(ast: &Program) -> Result<String, Diagnostic> {
     let mut output = String::new();
     let mut ctx = CodeGenContext::new();
+    ctx.structs = compute_layouts(&ast.structs);
+    for global in &ast.globals {
+        let decl = resolved_decl(global.decl, &global.name, global.span)?;
+        // .L: label locale al file, non si scontra con le funzioni o con gli helper
+        ctx.globals.insert(decl, format!(".Lglobal_{}", global.name));
+    }
 
     // header di riconoscimento compilatore sigma
     output.push_str("# Generato da Sigma Manny Compiler\n");
-    output.push_str("# Target: RISC-V RV32IM Linux\n\n");
+    output.push_str("# Target: RISC-V RV32IMF Linux\n\n");
 
     // dichiaro il main _start globale (.text)
     output.push_str("\n.text\n");
@@ -23,172 +33,252 @@ pub fn generate_riscv(ast: &Program) -> Result<String, String> {
         generate_function(&mut output, &mut ctx, function)?;
     }
 
-    // variabili globaili e funzioni (.data)
-    generate_data_section(&mut output, &mut ctx, ast)?;
+    // variabili globali, costanti e stringhe (.data, .rodata, .bss)
+    generate_data_section(&mut output, &mut ctx, &ast.globals)?;
 
     Ok(output)
 }
 
-fn generate_function(output: &mut String, ctx: &mut CodeGenContext, func: &Function) -> Result<(), String> {
+fn generate_function(output: &mut String, ctx: &mut CodeGenContext, func: &Function) -> Result<(), Diagnostic> {
     output.push_str(&format!("# Funzione {}\n", func.name));
     output.push_str(&format!("{}:\n", func.name));
 
     // Salva stato del contesto (ogni funzione ha scope separato)
     let saved_offset = ctx.stack_offset;
     let saved_vars = ctx.variables.clone();
-    let saved_types = ctx.variable_types.clone();
     ctx.stack_offset = 0;
     ctx.variables.clear();
-    ctx.variable_types.clear();
+    ctx.return_label = ctx.generate_label(&format!(".Lreturn_{}", func.name));
+    ctx.return_type = func.return_type.clone();
+
+    // Prima passata: ogni dichiarazione della funzione (parametri, variabili nei blocchi annidati,
+    // init dei sixSeven) riceve il suo offset da s0, così sappiamo quanto spazio serve davvero
+    ctx.locals_size = 0;
+    for param in &func.parameters {
+        let decl = resolved_decl(param.decl, &param.name, param.span)?;
+        ctx.allocate_variable(decl, param.parameter_type.clone());
+    }
+    layout_frame(ctx, &func.body)?;
 
-    // Contiamo quante variabili locali vi sono
-    let local_count = count_local_vars(&func.body);
-    let param_count = func.parameters.len();
+    // i temporanei delle espressioni stanno sopra alle variabili; quanti ne servono lo scopriamo
+    // generando il body, che quindi va in un buffer e il prologo si scrive dopo
+    ctx.temp_base = layout::align_to(ctx.locals_size, 4);
+    ctx.temp_offset = 0;
+    ctx.temp_size = 0;
 
-    // Calcola spazio necessario:
-    // - 4 byte per ogni variabile locale e parametro
-    // - 4 byte per il return address (ra)
-    // - Padding extra per temporanei durante valutazione espressioni
-    //   (le espressioni binarie usano stack per temporanei)
-    let max_expr_depth = estimate_max_expression_depth(&func.body);
-    let temp_space = max_expr_depth * 4;
-    let total_stack = (local_count + param_count) * 4 + 4 + temp_space;
-
-    // prologo della funzione in risc-v
-    // Usiamo s0 come frame pointer per accedere alle variabili
-    // anche quando sp viene modificato per temporanei
-
-    output.push_str("   # Prologo \n");
-    if total_stack > 0 {
-        output.push_str(&format!("  addi sp, sp, -{}\n", total_stack));
-    }
-    output.push_str("  mv s0, sp       # s0 = frame pointer\n");
-    output.push_str(&format!("  sw ra, {}(s0)\n", total_stack - 4));
+    let mut body = String::new();
 
     // salviamo i parametri nello stack
 
-    for (i, param) in func.parameters.iter().enumerate() {
-        let offset = ctx.allocate_variable(param.name.clone(), param.parameter_type.clone());
-        let reg = format!("a{}", i);
-        output.push_str(&format!("   sw {}, {}(s0) # param {}\n", reg, offset, param.name));
+    // un superBased arriva in due registri consecutivi (parte bassa, parte alta),
+    // i chill arrivano nei registri float fa0..fa7, che hanno una numerazione tutta loro
+    let mut reg = 0;
+    let mut float_reg = 0;
+    for param in &func.parameters {
+        let offset = variable_offset(ctx, param.decl, &param.name, param.span)?;
+        if param.parameter_type == Type::Chill {
+            let slot = memory_operand(&mut body, offset, "s0");
+            body.push_str(&format!("   fsw fa{}, {} # param {}\n", float_reg, slot, param.name));
+            float_reg += 1;
+            continue;
+        }
+        let slot = memory_operand(&mut body, offset, "s0");
+        body.push_str(&format!("   {} a{}, {} # param {}\n", store_instr(ctx, &param.parameter_type), reg, slot, param.name));
+        if is_long(&param.parameter_type) {
+            let slot = memory_operand(&mut body, offset + 4, "s0");
+            body.push_str(&format!("   sw a{}, {}\n", reg + 1, slot));
+            reg += 1;
+        }
+        reg += 1;
     }
 
     // generiamo il body in risc-v
     for stmt in &func.body {
-        generate_statement(output, ctx, stmt)?;
+        generate_statement(&mut body, ctx, stmt)?;
     }
 
-    // Epilogo della funzione in risc-v
+    // Calcola spazio necessario:
+    // - le variabili locali e i parametri
+    // - i temporanei delle espressioni
+    // - 4 byte per il return address (ra)
+    // - 4 byte per il frame pointer del chiamante (s0 è callee-saved)
+    // arrotondato a 16 byte: l'ABI RISC-V vuole sp sempre allineato a 16
+    let total_stack = layout::align_to(ctx.temp_base + ctx.temp_size + 8, 16);
 
-    output.push_str("   # Epilogo\n");
+    // prologo della funzione in risc-v
+    // Usiamo s0 come frame pointer per accedere alle variabili;
+    // dopo il prologo sp non si muove più fino all'epilogo
+
+    output.push_str("   # Prologo \n");
+    add_offset(output, "sp", "sp", -total_stack, "");
+    let slot = memory_operand(output, total_stack - 4, "sp");
+    output.push_str(&format!("  sw ra, {}\n", slot));
+    let slot = memory_operand(output, total_stack - 8, "sp");
+    output.push_str(&format!("  sw s0, {}   # salvo il frame pointer del chiamante\n", slot));
+    output.push_str("  mv s0, sp       # s0 = frame pointer\n");
+
+    output.push_str(&body);
+
+    // Epilogo della funzione in risc-v
 
     // Se la funzione è ghost (void) e non ha return esplicito, imposta a0 = 0
     if func.return_type == Type::Ghost {
         output.push_str("  li a0, 0        # funzione ghost ritorna 0\n");
     }
 
-    output.push_str(&format!("  lw ra, {}(s0)\n", total_stack - 4));
-    if total_stack > 0 {
-        output.push_str(&format!("  addi sp, sp, {}\n", total_stack));
-    }
+    // ogni yeet salta qui con il valore di ritorno già in a0
+    output.push_str(&format!("{}:\n", ctx.return_label));
+    output.push_str("   # Epilogo\n");
+    output.push_str("  mv sp, s0\n");
+    let slot = memory_operand(output, total_stack - 4, "sp");
+    output.push_str(&format!("  lw ra, {}\n", slot));
+    let slot = memory_operand(output, total_stack - 8, "sp");
+    output.push_str(&format!("  lw s0, {}\n", slot));
+    add_offset(output, "sp", "sp", total_stack, "");
 
     output.push_str("   ret\n\n");
 
     // ripristo il contesto precedente
     ctx.stack_offset = saved_offset;
     ctx.variables = saved_vars;
-    ctx.variable_types = saved_types;
 
     Ok(())
 }
 
-fn generate_statement(output: &mut String, ctx: &mut CodeGenContext, stmt: &Statement) -> Result<(), String> {
-    match stmt {
-        Statement::VarDecl { var_type, name, value} => {
+fn generate_statement(output: &mut String, ctx: &mut CodeGenContext, stmt: &Statement) -> Result<(), Diagnostic> {
+    match &stmt.kind {
+        StatementKind::VarDecl { var_type, name, value, decl } => {
             output.push_str(&format!("   # VarDecl: {} {}\n",
                                     type_to_string(var_type), name));
 
-            // allochiamo una bella variabile aahahaha
-            let offset = ctx.allocate_variable(name.clone(), var_type.clone());
+            // lo slot della variabile l'ha già scelto layout_frame
+            let offset = variable_offset(ctx, *decl, name, stmt.span)?;
 
-            // calcoliamo il valore (il risultato si troverà in a0)
-            generate_expression(output, ctx, value)?;
+            // calcoliamo il valore (il risultato si troverà in a0, e in a1 la parte alta dei superBased)
+            generate_expression_as(output, ctx, value, var_type)?;
 
             // salviamo nello stack (usa s0 come frame pointer)
-            output.push_str(&format!("  sw a0, {}(s0)   # salva in stack {}\n",
-                                     offset, name));
+            store_value(output, ctx, var_type, offset, "s0", name);
+            Ok(())
+        },
+
+        StatementKind::ArrayDecl { elem_type, name, size, decl } => {
+            // l'array vive direttamente nel frame, gli elementi non vengono inizializzati
+            let offset = variable_offset(ctx, *decl, name, stmt.span)?;
+            output.push_str(&format!("   # ArrayDecl: {} {}[{}] a {}(s0)\n",
+                                    type_to_string(elem_type), name, size, offset));
+            Ok(())
+        },
+
+        StatementKind::StructVarDecl { struct_name, name, decl } => {
+            // anche lo struct vive nel frame, i campi non vengono inizializzati
+            let offset = variable_offset(ctx, *decl, name, stmt.span)?;
+            output.push_str(&format!("   # StructVarDecl: {} {} a {}(s0)\n", struct_name, name, offset));
+            Ok(())
+        },
+
+        StatementKind::Store { target, value } => {
+            output.push_str("  # Store (elemento/campo)\n");
+            let target_type = expression_type(target)?;
+
+            // prima il valore, che salvo sullo stack
+            generate_expression_as(output, ctx, value, &target_type)?;
+            push_value(output, ctx, &target_type);
+
+            // poi l'indirizzo di destinazione, che sposto in t0
+            generate_address(output, ctx, target)?;
+            output.push_str("   mv t0, a0\n");
+
+            pop_value(output, ctx, &target_type, "a0", "a1");
+            store_value(output, ctx, &target_type, 0, "t0", "");
             Ok(())
         },
 
-        Statement::Assignment { name, value } => {
+        StatementKind::Assignment { name, value, decl, var_type } => {
             output.push_str(&format!("  # Assignment: {} =\n", name));
 
-            // troviamo l'offset della variabile
-            let offset = ctx.get_variable_offset(name)
-                .ok_or_else(|| format!("Variable '{}' not found", name))?;
+            // troviamo dove sta la variabile
+            let location = variable_location(ctx, *decl, name, stmt.span)?;
 
             // calcoliamo il nuovo valore
-            generate_expression(output, ctx, value)?;
-
-            // salviamo il valore all'offset della variabile (usa s0)
-            output.push_str(&format!("  sw a0, {}(s0)   # {} =\n",
-                                     offset, name));
+            let var_type = var_type.clone()
+                .ok_or_else(|| Diagnostic::error(stmt.span, format!("Type of variable '{}' not resolved", name)))?;
+            generate_expression_as(output, ctx, value, &var_type)?;
+
+            // salviamo il valore all'offset della variabile (usa s0), o alla label della globale
+            match location {
+                Location::Frame(offset) => store_value(output, ctx, &var_type, offset, "s0", name),
+                Location::Global(label) => {
+                    output.push_str(&format!("  la t0, {}\n", label));
+                    store_value(output, ctx, &var_type, 0, "t0", name);
+                }
+            }
             Ok(())
         },
-        Statement::Return { expr} => {
+        StatementKind::Return { expr} => {
             output.push_str("   # Return\n");
 
             if let Some(e) = expr {
                 // calcoliamo il valore di ritorno
-                generate_expression(output, ctx, e)?;
-                // il valore di ritorno si trova in a0
+                let return_type = ctx.return_type.clone();
+                generate_expression_as(output, ctx, e, &return_type)?;
+                // il valore di ritorno si trova in a0 (a0:a1 per i superBased, fa0 per i chill)
+                if return_type == Type::Chill {
+                    output.push_str("   fmv.w.x fa0, a0\n");
+                }
             } else {
                 // nessun valore di ritorno
                 output.push_str("   li a0, 0\n");
             }
 
-            // ret lo farà l'epilogo della funzione
+            // salto all'epilogo che ripristina lo stack e fa ret
+            output.push_str(&format!("   j    {}\n", ctx.return_label));
             Ok(())
         },
-        Statement::Break => {
-            if let Some(end_label) = ctx.current_loop_end() {
-                output.push_str(&format!("   j    {}      # ohio (break)\n", end_label));
-                Ok(())
-            } else {
-                Err("Break fuori da un loop porcodio!".to_string())
-            }
+        StatementKind::Call { name, args, signature } => {
+            output.push_str(&format!("   # Call: {}\n", name));
+
+            // il valore di ritorno in a0 viene semplicemente ignorato
+            generate_call(output, ctx, name, args, signature, stmt.span)
+        },
+        // il semantic non lascia passare ohio e fax fuori da un loop
+        StatementKind::Break => {
+            let end_label = ctx.current_loop_end().expect("ohio outside of a loop");
+            output.push_str(&format!("   j    {}      # ohio (break)\n", end_label));
+            Ok(())
+        },
+        StatementKind::Continue => {
+            let continue_label = ctx.current_loop_continue().expect("fax outside of a loop");
+            output.push_str(&format!("   j    {}      # fax (continue)\n", continue_label));
+            Ok(())
         },
 
-        Statement::Print { expr} => {
+        StatementKind::Print { expr} => {
             output.push_str("   # Print\n");
 
             // calcoliamo l'espressione da stampare
             generate_expression(output, ctx, expr)?;
 
-            // chiamiamo l'helper appropriato in base al tipo
-            let should_print_string = match expr {
-                Expression::StringLit(_) => true,
-                Expression::Variable(name) => {
-                    // controlliamo il tipo della variabile
-                    if let Some(var_type) = ctx.get_variable_type(name) {
-                        matches!(var_type, Type::Vibes)
-                    } else {
-                        false
-                    }
-                },
-                _ => false,
-            };
-
-            if should_print_string {
-                output.push_str("   call print_string\n");
-            } else {
-                output.push_str("   call print_int\n");
+            // chiamiamo l'helper appropriato in base al tipo che ha trovato il semantic
+            let expr_type = expr.resolved_type.as_ref()
+                .ok_or_else(|| Diagnostic::error(expr.span, "Type of printed expression not resolved"))?;
+            match expr_type {
+                Type::Based => output.push_str("   call print_int\n"),
+                Type::SuperBased => output.push_str("   call print_long\n"),
+                Type::Chill => output.push_str("   call print_float\n"),
+                Type::Chad => output.push_str("   call print_char\n"),
+                Type::Vibes => output.push_str("   call print_string\n"),
+                Type::NoCap => output.push_str("   call print_bool\n"),
+                // il semantic li ha già rifiutati
+                Type::Ghost | Type::Array(..) | Type::Struct(_) | Type::Pointer(_) => {
+                    return Err(Diagnostic::error(expr.span, format!("Cannot print a value of type {:?}", expr_type)));
+                }
             }
 
             Ok(())
         },
 
-        Statement::If { condition, then_body, else_body } => {
+        StatementKind::If { condition, then_body, else_body } => {
             // genero le label uniche per questo if
             let else_label = ctx.generate_label(".Lelse");
             let end_label = ctx.generate_label(".Lend_if");
@@ -203,9 +293,7 @@ fn generate_statement(output: &mut String, ctx: &mut CodeGenContext, stmt: &Stat
 
             // corpo del then
             output.push_str("   # Then body\n");
-            for stmt in then_body {
-                generate_statement(output, ctx, stmt)?;
-            }
+            generate_block(output, ctx, then_body)?;
 
             // salto alla fine dell'if (skippo l'else)
             output.push_str(&format!("   j    {}\n", end_label));
@@ -216,9 +304,7 @@ fn generate_statement(output: &mut String, ctx: &mut CodeGenContext, stmt: &Stat
             // corpo dell'else se c'è
             if let Some(stmts) = else_body {
                 output.push_str("   # Else body (nah)\n");
-                for stmt in stmts {
-                    generate_statement(output, ctx, stmt)?;
-                }
+                generate_block(output, ctx, stmts)?;
             }
 
             // label fine if
@@ -227,13 +313,13 @@ fn generate_statement(output: &mut String, ctx: &mut CodeGenContext, stmt: &Stat
             Ok(())
         },
 
-        Statement::While { condition, body } => {
+        StatementKind::While { condition, body } => {
             // genero le label uniche per questo while
             let start_label = ctx.generate_label(".Lwhile_start");
             let end_label = ctx.generate_label(".Lwhile_end");
 
-            // entro nel loop (per gestire break)
-            ctx.enter_loop(end_label.clone());
+            // entro nel loop (per gestire break e continue, che qui riparte dalla condizione)
+            ctx.enter_loop(end_label.clone(), start_label.clone());
 
             output.push_str("   # While loop (mewing)\n");
 
@@ -247,9 +333,7 @@ fn generate_statement(output: &mut String, ctx: &mut CodeGenContext, stmt: &Stat
             output.push_str(&format!("   beqz a0, {}\n", end_label));
 
             // corpo del while
-            for stmt in body {
-                generate_statement(output, ctx, stmt)?;
-            }
+            generate_block(output, ctx, body)?;
 
             // torno all'inizio del loop
             output.push_str(&format!("   j    {}\n", start_label));
@@ -263,10 +347,11 @@ fn generate_statement(output: &mut String, ctx: &mut CodeGenContext, stmt: &Stat
             Ok(())
         },
 
-        Statement::For { init, condition, increment, body } => {
+        StatementKind::For { init, condition, increment, body } => {
             // genero le label uniche per questo for
             let start_label = ctx.generate_label(".Lfor_start");
             let end_label = ctx.generate_label(".Lfor_end");
+            let continue_label = ctx.generate_label(".Lfor_continue");
 
             output.push_str("   # For loop (sixSeven)\n");
 
@@ -274,8 +359,8 @@ fn generate_statement(output: &mut String, ctx: &mut CodeGenContext, stmt: &Stat
             output.push_str("   # Init\n");
             generate_statement(output, ctx, init)?;
 
-            // entro nel loop
-            ctx.enter_loop(end_label.clone());
+            // entro nel loop: fax deve comunque eseguire l'incremento
+            ctx.enter_loop(end_label.clone(), continue_label.clone());
 
             // label inizio loop
             output.push_str(&format!("{}:\n", start_label));
@@ -289,11 +374,10 @@ fn generate_statement(output: &mut String, ctx: &mut CodeGenContext, stmt: &Stat
 
             // corpo del for
             output.push_str("   # Body\n");
-            for stmt in body {
-                generate_statement(output, ctx, stmt)?;
-            }
+            generate_block(output, ctx, body)?;
 
             // incremento
+            output.push_str(&format!("{}:\n", continue_label));
             output.push_str("   # Increment\n");
             generate_statement(output, ctx, increment)?;
 
@@ -311,44 +395,164 @@ fn generate_statement(output: &mut String, ctx: &mut CodeGenContext, stmt: &Stat
     }
 }
 
-fn generate_expression(output: &mut String, ctx: &mut CodeGenContext, expr: &Expression) -> Result<(), String> {
-    match expr {
-        Expression::Integer(n) => {
+// Corpo di un ong/nah/mewing/sixSeven (gli slot delle sue variabili li ha già scelti layout_frame)
+fn generate_block(output: &mut String, ctx: &mut CodeGenContext, stmts: &[Statement]) -> Result<(), Diagnostic> {
+    for stmt in stmts {
+        generate_statement(output, ctx, stmt)?;
+    }
+    Ok(())
+}
+
+fn generate_expression(output: &mut String, ctx: &mut CodeGenContext, expr: &Expression) -> Result<(), Diagnostic> {
+    match &expr.kind {
+        ExpressionKind::Integer(n) => {
             output.push_str(&format!("  li a0, {}\n", n));
             Ok(())
         },
-        Expression::Long(n) => {
-            // purtroppo in RV32 i registri sono a 32-bit quindi ci tocca troncare :(
+        ExpressionKind::Long(n) => {
+            // in RV32 i registri sono a 32-bit: la parte bassa va in a0, l'alta in a1
             output.push_str(&format!("  li a0, {}\n", *n as i32));
+            output.push_str(&format!("  li a1, {}\n", (*n >> 32) as i32));
+            Ok(())
+        },
+        ExpressionKind::Float(f) => {
+            // in a0 tengo i bit del float (f32): si spostano nei registri f solo per fare i conti
+            output.push_str(&format!("  li a0, {}   # {}\n", (*f as f32).to_bits() as i32, f));
             Ok(())
         },
-        Expression::CharLit(c) => {
+        ExpressionKind::CharLit(c) => {
             output.push_str(&format!("  li a0, {}\n", *c as i32));
             Ok(())
         },
-        Expression::StringLit(s) => {
+        ExpressionKind::Bool(b) => {
+            // fr = 1, cap = 0
+            output.push_str(&format!("  li a0, {}\n", *b as i32));
+            Ok(())
+        },
+        ExpressionKind::Null => {
+            output.push_str("  li a0, 0   # ghosted\n");
+            Ok(())
+        },
+        ExpressionKind::StringLit(s) => {
             let label = ctx.add_string_literal(s.clone());
             output.push_str(&format!("  la a0, {}\n", label));
             Ok(())
         },
-        Expression::Variable(name) => {
-            let offset = ctx.get_variable_offset(name)
-                .ok_or_else(|| format!("Variable '{}' not found", name))?;
-            output.push_str(&format!("  lw a0, {}(s0)   # load {}\n", offset, name));
+        ExpressionKind::Variable { name, decl } => {
+            let var_type = expression_type(expr)?;
+
+            match variable_location(ctx, *decl, name, expr.span)? {
+                // un array o uno struct usato come valore (es. passato a una funzione) è il suo indirizzo
+                Location::Frame(offset) if is_in_memory(&var_type) => {
+                    add_offset(output, "a0", "s0", offset, &format!("indirizzo di {}", name));
+                },
+                Location::Frame(offset) => load_value(output, ctx, &var_type, offset, "s0", name),
+                // una globale si legge passando dal suo indirizzo
+                Location::Global(label) => {
+                    output.push_str(&format!("  la a0, {}   # &{}\n", label, name));
+                    load_value(output, ctx, &var_type, 0, "a0", name);
+                }
+            }
             Ok(())
         },
-        Expression::BinOp {left, op, right} => {
+        // lettura dalla memoria: calcolo l'indirizzo e faccio lw/lbu attraverso a0
+        ExpressionKind::Index { .. } | ExpressionKind::Field { .. } | ExpressionKind::UnaryOp { op: UnaryOp::Deref, .. } => {
+            let value_type = expression_type(expr)?;
+            generate_address(output, ctx, expr)?;
+
+            if !is_in_memory(&value_type) {
+                load_value(output, ctx, &value_type, 0, "a0", "");
+            }
+            Ok(())
+        },
+        ExpressionKind::Call { name, args, signature } => generate_call(output, ctx, name, args, signature, expr.span),
+        ExpressionKind::BinOp { left, op: op @ (BinOp::And | BinOp::Or), right } => {
+            // Short-circuit: il lato destro si valuta solo se serve.
+            // Con && se left è cap il risultato è già cap, con || se left è fr è già fr
+            let end_label = ctx.generate_label(".Lshort_circuit");
+
+            generate_expression(output, ctx, left)?;
+            match op {
+                BinOp::And => output.push_str(&format!("   beqz a0, {}   # && : left è cap\n", end_label)),
+                _ => output.push_str(&format!("   bnez a0, {}   # || : left è fr\n", end_label)),
+            }
+
+            // i nocap sono già 0/1, quindi il risultato di right è il risultato di tutto
+            generate_expression(output, ctx, right)?;
+            output.push_str(&format!("{}:\n", end_label));
+
+            Ok(())
+        },
+        ExpressionKind::UnaryOp { op: UnaryOp::AddressOf, operand } => generate_address(output, ctx, operand),
+        ExpressionKind::UnaryOp { op: op @ (UnaryOp::Neg | UnaryOp::BitNot), operand } if is_long(&expression_type(operand)?) => {
+            generate_expression(output, ctx, operand)?;
+
+            // -x su 64 bit: neghi entrambe le parti e togli il prestito se la parte bassa non era 0
+            match op {
+                UnaryOp::Neg => {
+                    output.push_str("   snez t0, a0\n");
+                    output.push_str("   neg  a0, a0\n");
+                    output.push_str("   neg  a1, a1\n");
+                    output.push_str("   sub  a1, a1, t0\n");
+                },
+                _ => {
+                    output.push_str("   not  a0, a0\n");
+                    output.push_str("   not  a1, a1\n");
+                }
+            }
+
+            Ok(())
+        },
+        ExpressionKind::UnaryOp { op: UnaryOp::Neg, operand } if expression_type(operand)? == Type::Chill => {
+            generate_expression(output, ctx, operand)?;
+            output.push_str("   fmv.w.x ft0, a0\n");
+            output.push_str("   fneg.s ft0, ft0\n");
+            output.push_str("   fmv.x.w a0, ft0\n");
+            Ok(())
+        },
+        ExpressionKind::UnaryOp { op, operand } => {
+            generate_expression(output, ctx, operand)?;
+
+            match op {
+                UnaryOp::Neg => output.push_str("   neg  a0, a0\n"),
+                UnaryOp::Not => output.push_str("   xori a0, a0, 1   # !\n"),
+                UnaryOp::BitNot => output.push_str("   not  a0, a0\n"),
+                // gestiti sopra, lavorano sugli indirizzi
+                UnaryOp::AddressOf | UnaryOp::Deref => unreachable!()
+            }
+
+            Ok(())
+        },
+        ExpressionKind::BinOp { left, op, right } if is_long_operation(left, right)? => {
+            generate_long_binop(output, ctx, left, op, right)
+        },
+        ExpressionKind::BinOp { left, op, right } if is_float_operation(left, right)? => {
+            generate_float_binop(output, ctx, left, op, right)
+        },
+        ExpressionKind::BinOp { left, op, right } if expression_type(left)? == Type::Vibes => {
+            generate_string_binop(output, ctx, left, op, right)
+        },
+        ExpressionKind::BinOp {left, op, right} => {
             // generiamo left
             generate_expression(output, ctx, left)?;
-            output.push_str("   addi sp, sp, -4\n");
-            output.push_str("   sw a0, 0(sp)\n");
+            push_value(output, ctx, &Type::Based);
 
             // generiamo right
             generate_expression(output, ctx, right)?;
 
             // carico left in a1
-            output.push_str("   lw a1, 0(sp)\n");
-            output.push_str("   addi sp, sp, 4\n");
+            pop_value(output, ctx, &Type::Based, "a1", "");
+
+            // aritmetica dei puntatori: p + n si sposta di n elementi, non di n byte
+            let mut pointer_diff = None;
+            if matches!(op, BinOp::Add | BinOp::Sub) {
+                match (expression_type(left)?, expression_type(right)?) {
+                    (Type::Pointer(elem), Type::Pointer(_)) => pointer_diff = Some(ctx.type_size(&elem)),
+                    (Type::Pointer(elem), _) => scale_register(output, "a0", ctx.type_size(&elem)),
+                    (_, Type::Pointer(elem)) => scale_register(output, "a1", ctx.type_size(&elem)),
+                    _ => {}
+                }
+            }
 
             // eseguo l'operazione
             use BinOp::*;
@@ -357,6 +561,13 @@ fn generate_expression(output: &mut String, ctx: &mut CodeGenContext, expr: &Exp
                 Sub => output.push_str("    sub a0, a1, a0\n"),
                 Div => output.push_str("    div a0, a1, a0\n"),
                 Mul => output.push_str("    mul a0, a1, a0\n"),
+                Mod => output.push_str("    rem a0, a1, a0\n"),
+
+                BitAnd => output.push_str("    and a0, a1, a0\n"),
+                BitOr => output.push_str("    or  a0, a1, a0\n"),
+                BitXor => output.push_str("    xor a0, a1, a0\n"),
+                Shl => output.push_str("    sll a0, a1, a0\n"),
+                Shr => output.push_str("    sra a0, a1, a0\n"),
 
                 Equal => {
                     output.push_str("   sub a0, a1, a0\n");
@@ -375,14 +586,550 @@ fn generate_expression(output: &mut String, ctx: &mut CodeGenContext, expr: &Exp
                 LessEq => {
                     output.push_str("   slt a0, a0, a1\n");
                     output.push_str("   xori a0, a0, 1\n");
+                },
+                // gestiti sopra con lo short-circuit
+                And | Or => unreachable!()
+            }
+
+            // p - q: la differenza in byte diventa numero di elementi
+            if let Some(elem_size) = pointer_diff {
+                if elem_size.count_ones() == 1 {
+                    output.push_str(&format!("   srai a0, a0, {}\n", elem_size.trailing_zeros()));
+                } else {
+                    output.push_str(&format!("   li   t0, {}\n", elem_size));
+                    output.push_str("   div  a0, a0, t0\n");
                 }
             }
 
             Ok(())
+        },
+        // x as based: valuto x e converto il valore in a0 (a0:a1) al tipo di arrivo
+        ExpressionKind::Cast { expr: inner, target } => generate_expression_as(output, ctx, inner, target)
+    }
+}
+
+// Tipo di un'espressione (serve per scegliere load/store, scalare i puntatori, ...).
+// L'ha già calcolato il semantic e se l'è segnato nell'AST
+fn expression_type(expr: &Expression) -> Result<Type, Diagnostic> {
+    expr.resolved_type.clone()
+        .ok_or_else(|| Diagnostic::error(expr.span, "Type of expression not resolved"))
+}
+
+// Dichiarazione a cui il semantic ha legato una variabile
+fn resolved_decl(decl: Option<DeclId>, name: &str, span: Span) -> Result<DeclId, Diagnostic> {
+    decl.ok_or_else(|| Diagnostic::error(span, format!("Variable '{}' not resolved", name)))
+}
+
+// Dove sta una variabile: nel frame della funzione corrente o in una globale
+enum Location {
+    Frame(i32),     // offset da s0
+    Global(String)  // label in .data/.rodata/.bss
+}
+
+fn variable_location(ctx: &CodeGenContext, decl: Option<DeclId>, name: &str, span: Span) -> Result<Location, Diagnostic> {
+    let decl = resolved_decl(decl, name, span)?;
+    if let Some(offset) = ctx.get_variable_offset(decl) {
+        return Ok(Location::Frame(offset));
+    }
+    ctx.globals.get(&decl)
+        .map(|label| Location::Global(label.clone()))
+        .ok_or_else(|| Diagnostic::error(span, format!("Variable '{}' not found", name)))
+}
+
+// Offset da s0 dello slot che layout_frame ha dato alla variabile
+fn variable_offset(ctx: &CodeGenContext, decl: Option<DeclId>, name: &str, span: Span) -> Result<i32, Diagnostic> {
+    ctx.get_variable_offset(resolved_decl(decl, name, span)?)
+        .ok_or_else(|| Diagnostic::error(span, format!("Variable '{}' not found", name)))
+}
+
+// p.x con p puntatore a struct accede allo struct puntato
+fn pointee_struct(tipo: Type) -> Type {
+    match tipo {
+        Type::Pointer(inner) if matches!(*inner, Type::Struct(_)) => *inner,
+        other => other
+    }
+}
+
+// Moltiplica il registro per la dimensione di un elemento (shift se è una potenza di 2)
+fn scale_register(output: &mut String, reg: &str, elem_size: i32) {
+    if elem_size.count_ones() == 1 {
+        if elem_size > 1 {
+            output.push_str(&format!("   slli {}, {}, {}\n", reg, reg, elem_size.trailing_zeros()));
         }
+    } else {
+        output.push_str(&format!("   li   t0, {}\n", elem_size));
+        output.push_str(&format!("   mul  {}, {}, t0\n", reg, reg));
+    }
+}
+
+// Calcola in a0 l'indirizzo di una variabile, di arr[i] o di p.x
+fn generate_address(output: &mut String, ctx: &mut CodeGenContext, expr: &Expression) -> Result<(), Diagnostic> {
+    match &expr.kind {
+        ExpressionKind::Variable { name, decl } => {
+            match variable_location(ctx, *decl, name, expr.span)? {
+                Location::Frame(offset) => add_offset(output, "a0", "s0", offset, &format!("&{}", name)),
+                Location::Global(label) => output.push_str(&format!("   la   a0, {}   # &{}\n", label, name))
+            }
+            Ok(())
+        },
+        ExpressionKind::Index { array, index } => {
+            let elem_size = match expression_type(array)? {
+                Type::Array(elem, _) | Type::Pointer(elem) => ctx.type_size(&elem),
+                // s[i] su una vibes legge un chad
+                Type::Vibes => 1,
+                other => return Err(Diagnostic::error(expr.span, format!("Cannot index {:?}", other)))
+            };
+
+            // base dell'array: per un array locale è il suo indirizzo, per un parametro
+            // 'based v[]' o un puntatore è il valore salvato nello slot (generate_expression gestisce tutti)
+            generate_expression(output, ctx, array)?;
+            push_value(output, ctx, &Type::Based);
+
+            generate_expression(output, ctx, index)?;
+
+            // scalo l'indice per la dimensione dell'elemento
+            scale_register(output, "a0", elem_size);
+
+            pop_value(output, ctx, &Type::Based, "a1", "");
+            output.push_str("   add  a0, a1, a0\n");
+            Ok(())
+        },
+        ExpressionKind::Field { base, field } => {
+            let base_type = expression_type(base)?;
+            let through_pointer = matches!(base_type, Type::Pointer(_));
+            let struct_name = match pointee_struct(base_type) {
+                Type::Struct(name) => name,
+                other => return Err(Diagnostic::error(expr.span, format!("Cannot access field '{}' of {:?}", field, other)))
+            };
+            let (offset, _) = ctx.field(&struct_name, field)
+                .ok_or_else(|| Diagnostic::error(expr.span, format!("Struct '{}' has no field '{}'", struct_name, field)))?;
+
+            // con un puntatore l'indirizzo dello struct è il valore del puntatore stesso
+            if through_pointer {
+                generate_expression(output, ctx, base)?;
+            } else {
+                generate_address(output, ctx, base)?;
+            }
+            if offset != 0 {
+                // un campo dopo un array grande può stare oltre il range di addi
+                add_offset(output, "a0", "a0", offset, &format!(".{}", field));
+            }
+            Ok(())
+        },
+        // l'indirizzo di *p è semplicemente il valore di p
+        ExpressionKind::UnaryOp { op: UnaryOp::Deref, operand } => generate_expression(output, ctx, operand),
+        _ => Err(Diagnostic::error(expr.span, "Expression has no memory location"))
+    }
+}
+
+// Valuta expr per una destinazione di tipo `target`, convertendo il valore se serve
+// (es. un based che finisce in un superBased o in un chill)
+fn generate_expression_as(output: &mut String, ctx: &mut CodeGenContext, expr: &Expression, target: &Type) -> Result<(), Diagnostic> {
+    generate_expression(output, ctx, expr)?;
+    let from = expression_type(expr)?;
+    convert_value(output, &from, target);
+    Ok(())
+}
+
+// Converte il valore in a0 (a0:a1) dal tipo `from` al tipo `to`
+fn convert_value(output: &mut String, from: &Type, to: &Type) {
+    match (from, to) {
+        (Type::Based, Type::SuperBased) => {
+            output.push_str("   srai a1, a0, 31   # estensione del segno a 64 bit\n");
+        },
+        (Type::Based, Type::Chill) => {
+            output.push_str("   fcvt.s.w ft0, a0   # based -> chill\n");
+            output.push_str("   fmv.x.w a0, ft0\n");
+        },
+        (Type::Chill, Type::Based) => {
+            // come in C si tronca verso zero
+            output.push_str("   fmv.w.x ft0, a0\n");
+            output.push_str("   fcvt.w.s a0, ft0, rtz   # chill -> based\n");
+        },
+        (Type::Based, Type::Chad) => {
+            // un chad è un byte senza segno: tengo solo gli 8 bit bassi, come farebbe sb
+            output.push_str("   andi a0, a0, 255   # based -> chad\n");
+        },
+        // il testo del numero finisce in una stringa nuova sullo heap
+        (Type::Based, Type::Vibes) => output.push_str("   call int_to_str   # based -> vibes\n"),
+        (Type::Vibes, Type::Based) => output.push_str("   call str_to_int   # vibes -> based\n"),
+        // chad -> based è già a posto (lbu estende con zeri), superBased -> based tiene la parte bassa in a0,
+        // gyatt chad <-> vibes è lo stesso indirizzo
+        _ => {}
     }
 }
 
+// true se l'operazione va fatta sui float (basta un operando chill, l'altro viene convertito)
+fn is_float_operation(left: &Expression, right: &Expression) -> Result<bool, Diagnostic> {
+    Ok(expression_type(left)? == Type::Chill || expression_type(right)? == Type::Chill)
+}
+
+// Operazione sui chill: i bit degli operandi passano in ft0 (left) e ft1 (right),
+// il risultato torna in a0 (un nocap per i confronti)
+fn generate_float_binop(output: &mut String, ctx: &mut CodeGenContext, left: &Expression, op: &BinOp, right: &Expression) -> Result<(), Diagnostic> {
+    generate_expression_as(output, ctx, left, &Type::Chill)?;
+    push_value(output, ctx, &Type::Chill);
+
+    generate_expression_as(output, ctx, right, &Type::Chill)?;
+    pop_value(output, ctx, &Type::Chill, "a1", "");
+
+    output.push_str("    fmv.w.x ft0, a1\n");
+    output.push_str("    fmv.w.x ft1, a0\n");
+
+    use BinOp::*;
+    match op {
+        Add => output.push_str("    fadd.s ft0, ft0, ft1\n"),
+        Sub => output.push_str("    fsub.s ft0, ft0, ft1\n"),
+        Mul => output.push_str("    fmul.s ft0, ft0, ft1\n"),
+        Div => output.push_str("    fdiv.s ft0, ft0, ft1\n"),
+
+        Equal => {
+            output.push_str("    feq.s a0, ft0, ft1\n");
+            return Ok(());
+        },
+        NotEqual => {
+            output.push_str("    feq.s a0, ft0, ft1\n");
+            output.push_str("    xori a0, a0, 1\n");
+            return Ok(());
+        },
+        Less => {
+            output.push_str("    flt.s a0, ft0, ft1\n");
+            return Ok(());
+        },
+        Greater => {
+            output.push_str("    flt.s a0, ft1, ft0\n");
+            return Ok(());
+        },
+        LessEq => {
+            output.push_str("    fle.s a0, ft0, ft1\n");
+            return Ok(());
+        },
+        GreaterEq => {
+            output.push_str("    fle.s a0, ft1, ft0\n");
+            return Ok(());
+        },
+
+        // il semantic non li lascia passare sui chill
+        Mod | BitAnd | BitOr | BitXor | Shl | Shr | And | Or => unreachable!()
+    }
+
+    output.push_str("    fmv.x.w a0, ft0\n");
+    Ok(())
+}
+
+// Operazione tra vibes: + concatena in una stringa nuova sullo heap, i confronti
+// guardano il contenuto (non gli indirizzi) con str_compare, che torna <0, 0 o >0
+fn generate_string_binop(output: &mut String, ctx: &mut CodeGenContext, left: &Expression, op: &BinOp, right: &Expression) -> Result<(), Diagnostic> {
+    generate_expression(output, ctx, left)?;
+    push_value(output, ctx, &Type::Vibes);
+
+    generate_expression(output, ctx, right)?;
+    output.push_str("   mv   a1, a0\n");
+    pop_value(output, ctx, &Type::Vibes, "a0", "");
+
+    use BinOp::*;
+    if let Add = op {
+        output.push_str("   call str_concat\n");
+        return Ok(());
+    }
+
+    output.push_str("   call str_compare\n");
+    match op {
+        Equal => output.push_str("   seqz a0, a0\n"),
+        NotEqual => output.push_str("   snez a0, a0\n"),
+        Less => output.push_str("   sltz a0, a0\n"),
+        Greater => output.push_str("   sgtz a0, a0\n"),
+        LessEq => {
+            output.push_str("   sgtz a0, a0\n");
+            output.push_str("   xori a0, a0, 1\n");
+        },
+        GreaterEq => {
+            output.push_str("   sltz a0, a0\n");
+            output.push_str("   xori a0, a0, 1\n");
+        },
+        // + è gestito sopra, il resto il semantic non lo lascia passare sulle vibes
+        Add | Sub | Mul | Div | Mod | BitAnd | BitOr | BitXor | Shl | Shr | And | Or => unreachable!()
+    }
+
+    Ok(())
+}
+
+// true se l'operazione va fatta a 64 bit (basta un operando superBased)
+fn is_long_operation(left: &Expression, right: &Expression) -> Result<bool, Diagnostic> {
+    Ok(is_long(&expression_type(left)?) || is_long(&expression_type(right)?))
+}
+
+// Operazione a 64 bit: entrambi gli operandi vengono estesi, left finisce in a2:a3
+// e right in a0:a1 (parte bassa, parte alta). Il risultato va in a0:a1, i confronti in a0
+fn generate_long_binop(output: &mut String, ctx: &mut CodeGenContext, left: &Expression, op: &BinOp, right: &Expression) -> Result<(), Diagnostic> {
+    generate_expression_as(output, ctx, left, &Type::SuperBased)?;
+    push_value(output, ctx, &Type::SuperBased);
+
+    generate_expression_as(output, ctx, right, &Type::SuperBased)?;
+    pop_value(output, ctx, &Type::SuperBased, "a2", "a3");
+
+    use BinOp::*;
+    match op {
+        Add => {
+            output.push_str("    add  a0, a2, a0\n");
+            output.push_str("    sltu t0, a0, a2   # riporto della parte bassa\n");
+            output.push_str("    add  a1, a3, a1\n");
+            output.push_str("    add  a1, a1, t0\n");
+        },
+        Sub => {
+            output.push_str("    sltu t0, a2, a0   # prestito dalla parte alta\n");
+            output.push_str("    sub  a0, a2, a0\n");
+            output.push_str("    sub  a1, a3, a1\n");
+            output.push_str("    sub  a1, a1, t0\n");
+        },
+        Mul => {
+            // dei 128 bit del prodotto servono i 64 bassi: lo*lo intero (mul + mulhu)
+            // più i prodotti incrociati, che finiscono solo nella parte alta
+            output.push_str("    mul  t0, a2, a1\n");
+            output.push_str("    mul  t1, a3, a0\n");
+            output.push_str("    add  t0, t0, t1\n");
+            output.push_str("    mulhu t1, a2, a0\n");
+            output.push_str("    add  a1, t0, t1\n");
+            output.push_str("    mul  a0, a2, a0\n");
+        },
+        Div | Mod => {
+            // divmod_long vuole il dividendo in a0:a1 e il divisore in a2:a3
+            output.push_str("    mv   t0, a0\n");
+            output.push_str("    mv   t1, a1\n");
+            output.push_str("    mv   a0, a2\n");
+            output.push_str("    mv   a1, a3\n");
+            output.push_str("    mv   a2, t0\n");
+            output.push_str("    mv   a3, t1\n");
+            output.push_str("    call divmod_long\n");
+            if let Mod = op {
+                output.push_str("    mv   a0, a2   # resto\n");
+                output.push_str("    mv   a1, a3\n");
+            }
+        },
+
+        BitAnd => {
+            output.push_str("    and  a0, a2, a0\n");
+            output.push_str("    and  a1, a3, a1\n");
+        },
+        BitOr => {
+            output.push_str("    or   a0, a2, a0\n");
+            output.push_str("    or   a1, a3, a1\n");
+        },
+        BitXor => {
+            output.push_str("    xor  a0, a2, a0\n");
+            output.push_str("    xor  a1, a3, a1\n");
+        },
+        Shl | Shr => {
+            // valore in a0:a1, numero di bit (la parte bassa di right) in a2
+            output.push_str("    mv   t0, a0\n");
+            output.push_str("    mv   a0, a2\n");
+            output.push_str("    mv   a1, a3\n");
+            output.push_str("    mv   a2, t0\n");
+            match op {
+                Shl => output.push_str("    call shl_long\n"),
+                _ => output.push_str("    call sra_long\n"),
+            }
+        },
+
+        Equal | NotEqual => {
+            output.push_str("    xor  t0, a2, a0\n");
+            output.push_str("    xor  t1, a3, a1\n");
+            output.push_str("    or   a0, t0, t1\n");
+            match op {
+                Equal => output.push_str("    seqz a0, a0\n"),
+                _ => output.push_str("    snez a0, a0\n"),
+            }
+        },
+        Less => long_less_than(output, ("a2", "a3"), ("a0", "a1")),
+        Greater => long_less_than(output, ("a0", "a1"), ("a2", "a3")),
+        GreaterEq => {
+            long_less_than(output, ("a2", "a3"), ("a0", "a1"));
+            output.push_str("    xori a0, a0, 1\n");
+        },
+        LessEq => {
+            long_less_than(output, ("a0", "a1"), ("a2", "a3"));
+            output.push_str("    xori a0, a0, 1\n");
+        },
+        // gestiti con lo short-circuit, e comunque lavorano solo su nocap
+        And | Or => unreachable!()
+    }
+
+    Ok(())
+}
+
+// a0 = (lhs < rhs) su 64 bit con segno: decidono le parti alte (con segno),
+// se sono uguali decidono le parti basse (senza segno)
+fn long_less_than(output: &mut String, lhs: (&str, &str), rhs: (&str, &str)) {
+    output.push_str(&format!("    slt  t0, {}, {}\n", lhs.1, rhs.1));
+    output.push_str(&format!("    xor  t1, {}, {}\n", lhs.1, rhs.1));
+    output.push_str("    seqz t1, t1\n");
+    output.push_str(&format!("    sltu t2, {}, {}\n", lhs.0, rhs.0));
+    output.push_str("    and  t2, t2, t1\n");
+    output.push_str("    or   a0, t0, t2\n");
+}
+
+fn is_long(tipo: &Type) -> bool {
+    matches!(tipo, Type::SuperBased)
+}
+
+// Salva il valore in a0 (a0:a1) in uno slot temporaneo del frame e ritorna il suo offset da s0
+fn push_value(output: &mut String, ctx: &mut CodeGenContext, tipo: &Type) -> i32 {
+    let size = if is_long(tipo) { 8 } else { 4 };
+    let offset = ctx.push_temp(size);
+    let slot = memory_operand(output, offset, "s0");
+    output.push_str(&format!("   sw a0, {}   # temporaneo\n", slot));
+    if is_long(tipo) {
+        let slot = memory_operand(output, offset + 4, "s0");
+        output.push_str(&format!("   sw a1, {}\n", slot));
+    }
+    offset
+}
+
+// Riprende l'ultimo valore salvato con push_value, nei registri lo (e hi)
+fn pop_value(output: &mut String, ctx: &mut CodeGenContext, tipo: &Type, lo: &str, hi: &str) {
+    let size = if is_long(tipo) { 8 } else { 4 };
+    let offset = ctx.pop_temp(size);
+    let slot = memory_operand(output, offset, "s0");
+    output.push_str(&format!("   lw {}, {}\n", lo, slot));
+    if is_long(tipo) {
+        let slot = memory_operand(output, offset + 4, "s0");
+        output.push_str(&format!("   lw {}, {}\n", hi, slot));
+    }
+}
+
+// Carica in a0 il valore che si trova a offset(base); per un superBased anche la parte
+// alta in a1, letta per prima così base può essere proprio a0
+fn load_value(output: &mut String, ctx: &CodeGenContext, tipo: &Type, offset: i32, base: &str, comment: &str) {
+    if is_long(tipo) {
+        let slot = memory_operand(output, offset + 4, base);
+        output.push_str(&format!("  lw a1, {}\n", slot));
+    }
+    let slot = memory_operand(output, offset, base);
+    output.push_str(&format!("  {} a0, {}", load_instr(ctx, tipo), slot));
+    if !comment.is_empty() {
+        output.push_str(&format!("   # load {}", comment));
+    }
+    output.push('\n');
+}
+
+// Scrive a0 (e a1 per i superBased) a offset(base)
+fn store_value(output: &mut String, ctx: &CodeGenContext, tipo: &Type, offset: i32, base: &str, comment: &str) {
+    let slot = memory_operand(output, offset, base);
+    output.push_str(&format!("  {} a0, {}", store_instr(ctx, tipo), slot));
+    if !comment.is_empty() {
+        output.push_str(&format!("   # {} =", comment));
+    }
+    output.push('\n');
+    if is_long(tipo) {
+        let slot = memory_operand(output, offset + 4, base);
+        output.push_str(&format!("  sw a1, {}\n", slot));
+    }
+}
+
+// Le immediate di addi, load e store hanno 12 bit con segno: un frame con un array grande
+// ha slot oltre 2047 byte da s0, e lì l'indirizzo va calcolato a parte in t6, che il codice
+// delle funzioni non usa per nient'altro
+fn fits_immediate(value: i32) -> bool {
+    (-2048..=2047).contains(&value)
+}
+
+// Operando di memoria per offset(base), da usare subito nella load o nella store
+fn memory_operand(output: &mut String, offset: i32, base: &str) -> String {
+    if fits_immediate(offset) {
+        return format!("{}({})", offset, base);
+    }
+    output.push_str(&format!("   li   t6, {}\n", offset));
+    output.push_str(&format!("   add  t6, {}, t6\n", base));
+    "0(t6)".to_string()
+}
+
+// dest = base + offset, con addi quando l'offset ci sta
+fn add_offset(output: &mut String, dest: &str, base: &str, offset: i32, comment: &str) {
+    if fits_immediate(offset) {
+        output.push_str(&format!("   addi {}, {}, {}", dest, base, offset));
+    } else {
+        output.push_str(&format!("   li   t6, {}\n", offset));
+        output.push_str(&format!("   add  {}, {}, t6", dest, base));
+    }
+    if !comment.is_empty() {
+        output.push_str(&format!("   # {}", comment));
+    }
+    output.push('\n');
+}
+
+// Array locali e struct stanno direttamente nel frame: il loro "valore" è l'indirizzo
+fn is_in_memory(tipo: &Type) -> bool {
+    matches!(tipo, Type::Array(_, Some(_)) | Type::Struct(_))
+}
+
+// Istruzioni di load/store giuste per la dimensione del tipo (chad e nocap occupano un byte)
+fn load_instr(ctx: &CodeGenContext, tipo: &Type) -> &'static str {
+    if ctx.type_size(tipo) == 1 { "lbu" } else { "lw" }
+}
+
+fn store_instr(ctx: &CodeGenContext, tipo: &Type) -> &'static str {
+    if ctx.type_size(tipo) == 1 { "sb" } else { "sw" }
+}
+
+// Chiamata a funzione: valuto gli argomenti uno alla volta salvandoli nei temporanei
+// (un argomento può contenere a sua volta una chiamata che sporca a0..a7),
+// poi li carico in a0..a7 e faccio call. Un superBased occupa due registri di fila.
+// Il risultato resta in a0 (a0:a1 per i superBased).
+fn generate_call(output: &mut String, ctx: &mut CodeGenContext, name: &str, args: &[Expression], signature: &Option<Signature>, span: Span) -> Result<(), Diagnostic> {
+    let signature = signature.as_ref()
+        .ok_or_else(|| Diagnostic::error(span, format!("Call to '{}' not resolved", name)))?;
+    let param_types = &signature.parameters;
+
+    let registers: usize = param_types.iter().map(|t| match t {
+        Type::SuperBased => 2,
+        Type::Chill => 0,
+        _ => 1
+    }).sum();
+    let float_registers = param_types.iter().filter(|t| **t == Type::Chill).count();
+    if registers > 8 || float_registers > 8 {
+        return Err(Diagnostic::error(span, format!("Too many arguments in call to '{}': at most 8 registers are supported", name)));
+    }
+
+    let mut offsets = Vec::new();
+    for (arg, param_type) in args.iter().zip(param_types) {
+        generate_expression_as(output, ctx, arg, param_type)?;
+        offsets.push(push_value(output, ctx, param_type));
+    }
+
+    // tutti gli argomenti sono nei temporanei: li carico nei registri e libero gli slot
+    let mut reg = 0;
+    let mut float_reg = 0;
+    for (offset, param_type) in offsets.iter().zip(param_types) {
+        if *param_type == Type::Chill {
+            let slot = memory_operand(output, *offset, "s0");
+            output.push_str(&format!("   flw fa{}, {}\n", float_reg, slot));
+            float_reg += 1;
+            continue;
+        }
+        let slot = memory_operand(output, *offset, "s0");
+        output.push_str(&format!("   lw a{}, {}\n", reg, slot));
+        if is_long(param_type) {
+            let slot = memory_operand(output, offset + 4, "s0");
+            output.push_str(&format!("   lw a{}, {}\n", reg + 1, slot));
+            reg += 1;
+        }
+        reg += 1;
+    }
+
+    let total: i32 = param_types.iter().map(|t| if is_long(t) { 8 } else { 4 }).sum();
+    ctx.pop_temp(total);
+
+    let label = builtin(name).map_or(name, |builtin| builtin.helper);
+    output.push_str(&format!("   call {}\n", label));
+
+    // un chill torna in fa0, ma il resto del codegen lo vuole in a0
+    if signature.return_type == Type::Chill {
+        output.push_str("   fmv.x.w a0, fa0\n");
+    }
+
+    Ok(())
+}
+
 fn type_to_string(var_type: &Type) -> &str {
     match var_type {
         Type::Based => "based",
@@ -390,66 +1137,60 @@ fn type_to_string(var_type: &Type) -> &str {
         Type::Chad => "chad",
         Type::SuperBased => "superBased",
         Type::Vibes => "vibes",
-        Type::Ghost => "ghost"
+        Type::NoCap => "nocap",
+        Type::Ghost => "ghost",
+        Type::Array(..) => "array",
+        Type::Struct(_) => "skibidi",
+        Type::Pointer(_) => "gyatt"
     }
 }
 
-fn count_local_vars(stmts: &Vec<Statement>) -> usize {
-    stmts.iter()
-        .filter(|s| matches!(s, Statement::VarDecl { .. }))
-        .count()
-}
-
-// Stima la profondità massima delle espressioni annidate
-// Ogni livello di BinOp richiede 4 byte sullo stack per temporanei
-fn estimate_max_expression_depth(stmts: &Vec<Statement>) -> usize {
-    let mut max_depth = 0;
+// Prima passata sul body: dà uno slot a ogni dichiarazione, anche dentro blocchi annidati e
+// nell'init dei sixSeven, con gli stessi scope del semantic. Quando un blocco si chiude i suoi
+// slot tornano liberi e il blocco dopo li riusa; ctx.locals_size tiene il punto più profondo
+fn layout_frame(ctx: &mut CodeGenContext, stmts: &[Statement]) -> Result<(), Diagnostic> {
     for stmt in stmts {
-        let depth = statement_expr_depth(stmt);
-        if depth > max_depth {
-            max_depth = depth;
-        }
-    }
-    // Aggiungi un buffer di sicurezza
-    max_depth + 4
-}
-
-fn statement_expr_depth(stmt: &Statement) -> usize {
-    match stmt {
-        Statement::VarDecl { value, .. } => expr_depth(value),
-        Statement::Assignment { value, .. } => expr_depth(value),
-        Statement::Print { expr } => expr_depth(expr),
-        Statement::Return { expr: Some(e) } => expr_depth(e),
-        Statement::If { condition, then_body, else_body } => {
-            let cond_depth = expr_depth(condition);
-            let then_depth = then_body.iter().map(|s| statement_expr_depth(s)).max().unwrap_or(0);
-            let else_depth = else_body.as_ref()
-                .map(|blk| blk.iter().map(|s| statement_expr_depth(s)).max().unwrap_or(0))
-                .unwrap_or(0);
-            cond_depth.max(then_depth).max(else_depth)
-        }
-        Statement::While { condition, body } => {
-            let cond_depth = expr_depth(condition);
-            let body_depth = body.iter().map(|s| statement_expr_depth(s)).max().unwrap_or(0);
-            cond_depth.max(body_depth)
+        match &stmt.kind {
+            StatementKind::VarDecl { var_type, name, decl, .. } => {
+                ctx.allocate_variable(resolved_decl(*decl, name, stmt.span)?, var_type.clone());
+            },
+            StatementKind::ArrayDecl { elem_type, name, size, decl } => {
+                ctx.allocate_variable(resolved_decl(*decl, name, stmt.span)?, Type::Array(Box::new(elem_type.clone()), Some(*size)));
+            },
+            StatementKind::StructVarDecl { struct_name, name, decl } => {
+                ctx.allocate_variable(resolved_decl(*decl, name, stmt.span)?, Type::Struct(struct_name.clone()));
+            },
+            StatementKind::If { then_body, else_body, .. } => {
+                layout_block(ctx, then_body)?;
+                if let Some(stmts) = else_body {
+                    layout_block(ctx, stmts)?;
+                }
+            },
+            StatementKind::While { body, .. } => layout_block(ctx, body)?,
+            StatementKind::For { init, body, .. } => {
+                // la variabile dell'init vive per tutto il for, il body ha uno scope suo dentro
+                ctx.enter_scope();
+                layout_frame(ctx, std::slice::from_ref(init.as_ref()))?;
+                layout_block(ctx, body)?;
+                ctx.exit_scope();
+            },
+            _ => {}
         }
-        _ => 0
     }
+    Ok(())
 }
 
-fn expr_depth(expr: &Expression) -> usize {
-    match expr {
-        Expression::BinOp { left, right, .. } => {
-            1 + expr_depth(left).max(expr_depth(right))
-        }
-        _ => 0
-    }
+fn layout_block(ctx: &mut CodeGenContext, stmts: &[Statement]) -> Result<(), Diagnostic> {
+    ctx.enter_scope();
+    layout_frame(ctx, stmts)?;
+    ctx.exit_scope();
+    Ok(())
 }
 
 fn generate_helpers(output: &mut String) {
     output.push_str("# Helper: stampa di numero intero in a0\n");
     output.push_str("print_int:\n");
-    output.push_str("   addi sp, sp, -20\n");
+    output.push_str("   addi sp, sp, -32    # buffer per le cifre, arrotondato a 16\n");
     output.push_str("   mv t0, sp\n");
     output.push_str("   li t1, 10\n");
     output.push_str("   mv t2, a0\n");
@@ -492,20 +1233,20 @@ fn generate_helpers(output: &mut String) {
     output.push_str("   li   a2, 1\n");
     output.push_str("   li   a7, 64\n");
     output.push_str("   ecall\n");
-    output.push_str("   addi sp, sp, 20\n");
+    output.push_str("   addi sp, sp, 32\n");
     output.push_str("   ret\n\n");
 
     // Helper per stampare stringhe (implementato da me diocristo)
     output.push_str("# Stampa stringa (vibes) in a0 (implementato da me porca puttana)\n");
     output.push_str("print_string:\n");
-    output.push_str("   addi sp, sp, -4\n");
+    output.push_str("   addi sp, sp, -16\n");
     output.push_str("   sw   a0, 0(sp)      # salvo l'indirizzo originale\n");
     output.push_str("   # calcolo la lunghezza della stringa\n");
     output.push_str("   mv   t0, a0         # t0 = puntatore stringa\n");
     output.push_str("   li   t1, 0          # t1 = contatore lunghezza\n");
     output.push_str(".Lstrlen_loop:\n");
     output.push_str("   lb   t2, 0(t0)      # carico byte corrente\n");
-    output.push_str("   beqz t2, .Lstr_write # se '\0' (null terminator) stampa\n");
+    output.push_str("   beqz t2, .Lstr_write # se '\\0' (null terminator) stampa\n");
     output.push_str("   addi t0, t0, 1      # vado al prossimo carattere\n");
     output.push_str("   addi t1, t1, 1      # incremento il contatore\n");
     output.push_str("   j    .Lstrlen_loop  # continuo il loop dio boia\n");
@@ -522,7 +1263,659 @@ fn generate_helpers(output: &mut String) {
     output.push_str("   li   a2, 1\n");
     output.push_str("   li   a7, 64\n");
     output.push_str("   ecall\n");
-    output.push_str("   addi sp, sp, 4      # ripristino stack\n");
+    output.push_str("   addi sp, sp, 16     # ripristino stack\n");
+    output.push_str("   ret\n\n");
+
+    // Un chad si stampa come carattere: il byte e il newline in un'unica write
+    output.push_str("# Stampa carattere (chad) in a0\n");
+    output.push_str("print_char:\n");
+    output.push_str("   addi sp, sp, -16\n");
+    output.push_str("   sb   a0, 0(sp)      # il carattere\n");
+    output.push_str("   li   t0, 10\n");
+    output.push_str("   sb   t0, 1(sp)      # seguito da '\\n'\n");
+    output.push_str("   li   a0, 1          # stdout\n");
+    output.push_str("   mv   a1, sp\n");
+    output.push_str("   li   a2, 2\n");
+    output.push_str("   li   a7, 64         # syscall write\n");
+    output.push_str("   ecall\n");
+    output.push_str("   addi sp, sp, 16\n");
+    output.push_str("   ret\n\n");
+
+    // Un nocap si stampa come la sua keyword: passo la stringa giusta a print_string
+    output.push_str("# Stampa booleano (nocap) in a0 come fr / cap\n");
+    output.push_str("print_bool:\n");
+    output.push_str("   beqz a0, .Lbool_cap\n");
+    output.push_str("   la   a0, .Lfr\n");
+    output.push_str("   j    print_string\n");
+    output.push_str(".Lbool_cap:\n");
+    output.push_str("   la   a0, .Lcap\n");
+    output.push_str("   j    print_string   # tail call, ritorna direttamente al chiamante\n\n");
+
+    generate_long_helpers(output);
+    generate_float_helpers(output);
+    generate_input_helpers(output);
+    generate_heap_helpers(output);
+    generate_string_helpers(output);
+}
+
+// Helper per i superBased, che in RV32 stanno in una coppia di registri (bassa, alta)
+fn generate_long_helpers(output: &mut String) {
+    // Divisione senza segno a 64 bit, una cifra binaria alla volta (shift and subtract).
+    // a0:a1 / a2:a3 -> quoziente in a0:a1, resto in a2:a3. Usa solo t0..t4
+    output.push_str("# Divisione senza segno a 64 bit: a0:a1 / a2:a3\n");
+    output.push_str("divmodu_long:\n");
+    output.push_str("   li   t2, 0          # t2:t3 = resto parziale\n");
+    output.push_str("   li   t3, 0\n");
+    output.push_str("   li   t4, 64         # un giro per bit\n");
+    output.push_str(".Ldivu_loop:\n");
+    output.push_str("   # resto <<= 1, entrando col bit più alto del dividendo\n");
+    output.push_str("   slli t3, t3, 1\n");
+    output.push_str("   srli t0, t2, 31\n");
+    output.push_str("   or   t3, t3, t0\n");
+    output.push_str("   slli t2, t2, 1\n");
+    output.push_str("   srli t0, a1, 31\n");
+    output.push_str("   or   t2, t2, t0\n");
+    output.push_str("   # dividendo <<= 1, il bit basso che si libera diventa il bit del quoziente\n");
+    output.push_str("   slli a1, a1, 1\n");
+    output.push_str("   srli t0, a0, 31\n");
+    output.push_str("   or   a1, a1, t0\n");
+    output.push_str("   slli a0, a0, 1\n");
+    output.push_str("   # se resto >= divisore: resto -= divisore e il bit vale 1\n");
+    output.push_str("   bltu t3, a3, .Ldivu_next\n");
+    output.push_str("   bne  t3, a3, .Ldivu_sub\n");
+    output.push_str("   bltu t2, a2, .Ldivu_next\n");
+    output.push_str(".Ldivu_sub:\n");
+    output.push_str("   sltu t0, t2, a2\n");
+    output.push_str("   sub  t2, t2, a2\n");
+    output.push_str("   sub  t3, t3, a3\n");
+    output.push_str("   sub  t3, t3, t0\n");
+    output.push_str("   ori  a0, a0, 1\n");
+    output.push_str(".Ldivu_next:\n");
+    output.push_str("   addi t4, t4, -1\n");
+    output.push_str("   bnez t4, .Ldivu_loop\n");
+    output.push_str("   mv   a2, t2\n");
+    output.push_str("   mv   a3, t3\n");
+    output.push_str("   ret\n\n");
+
+    // Versione con segno: divide i valori assoluti e poi sistema i segni come div/rem
+    // (quoziente troncato verso zero, il resto ha il segno del dividendo)
+    output.push_str("# Divisione con segno a 64 bit: quoziente in a0:a1, resto in a2:a3\n");
+    output.push_str("divmod_long:\n");
+    output.push_str("   addi sp, sp, -16    # sp resta allineato a 16 anche durante la call\n");
+    output.push_str("   sw   ra, 12(sp)\n");
+    output.push_str("   srai t6, a1, 31     # t6 != 0 se il dividendo è negativo (segno del resto)\n");
+    output.push_str("   srai t5, a3, 31\n");
+    output.push_str("   xor  t5, t5, t6     # t5 != 0 se il quoziente è negativo\n");
+    output.push_str("   bgez a1, .Ldivl_divisor\n");
+    output.push_str("   snez t0, a0\n");
+    output.push_str("   neg  a0, a0\n");
+    output.push_str("   neg  a1, a1\n");
+    output.push_str("   sub  a1, a1, t0\n");
+    output.push_str(".Ldivl_divisor:\n");
+    output.push_str("   bgez a3, .Ldivl_divide\n");
+    output.push_str("   snez t0, a2\n");
+    output.push_str("   neg  a2, a2\n");
+    output.push_str("   neg  a3, a3\n");
+    output.push_str("   sub  a3, a3, t0\n");
+    output.push_str(".Ldivl_divide:\n");
+    output.push_str("   call divmodu_long   # non tocca t5 e t6\n");
+    output.push_str("   beqz t5, .Ldivl_rem_sign\n");
+    output.push_str("   snez t0, a0\n");
+    output.push_str("   neg  a0, a0\n");
+    output.push_str("   neg  a1, a1\n");
+    output.push_str("   sub  a1, a1, t0\n");
+    output.push_str(".Ldivl_rem_sign:\n");
+    output.push_str("   beqz t6, .Ldivl_done\n");
+    output.push_str("   snez t0, a2\n");
+    output.push_str("   neg  a2, a2\n");
+    output.push_str("   neg  a3, a3\n");
+    output.push_str("   sub  a3, a3, t0\n");
+    output.push_str(".Ldivl_done:\n");
+    output.push_str("   lw   ra, 12(sp)\n");
+    output.push_str("   addi sp, sp, 16\n");
+    output.push_str("   ret\n\n");
+
+    // Shift a 64 bit di a2 posizioni (modulo 64): oltre 32 una parte passa tutta nell'altra
+    output.push_str("# Shift a sinistra a 64 bit: a0:a1 << a2\n");
+    output.push_str("shl_long:\n");
+    output.push_str("   andi a2, a2, 63\n");
+    output.push_str("   li   t0, 32\n");
+    output.push_str("   blt  a2, t0, .Lshl_small\n");
+    output.push_str("   addi a2, a2, -32\n");
+    output.push_str("   sll  a1, a0, a2\n");
+    output.push_str("   li   a0, 0\n");
+    output.push_str("   ret\n");
+    output.push_str(".Lshl_small:\n");
+    output.push_str("   beqz a2, .Lshl_done  # shift di 32 non si può fare, quindi lo 0 va a parte\n");
+    output.push_str("   sll  a1, a1, a2\n");
+    output.push_str("   sub  t0, t0, a2\n");
+    output.push_str("   srl  t1, a0, t0     # i bit che passano dalla parte bassa all'alta\n");
+    output.push_str("   or   a1, a1, t1\n");
+    output.push_str("   sll  a0, a0, a2\n");
+    output.push_str(".Lshl_done:\n");
+    output.push_str("   ret\n\n");
+
+    output.push_str("# Shift aritmetico a destra a 64 bit: a0:a1 >> a2\n");
+    output.push_str("sra_long:\n");
+    output.push_str("   andi a2, a2, 63\n");
+    output.push_str("   li   t0, 32\n");
+    output.push_str("   blt  a2, t0, .Lsra_small\n");
+    output.push_str("   addi a2, a2, -32\n");
+    output.push_str("   sra  a0, a1, a2\n");
+    output.push_str("   srai a1, a1, 31\n");
+    output.push_str("   ret\n");
+    output.push_str(".Lsra_small:\n");
+    output.push_str("   beqz a2, .Lsra_done\n");
+    output.push_str("   srl  a0, a0, a2\n");
+    output.push_str("   sub  t0, t0, a2\n");
+    output.push_str("   sll  t1, a1, t0     # i bit che passano dalla parte alta alla bassa\n");
+    output.push_str("   or   a0, a0, t1\n");
+    output.push_str("   sra  a1, a1, a2\n");
+    output.push_str(".Lsra_done:\n");
+    output.push_str("   ret\n\n");
+
+    // Come print_int, ma le cifre si ottengono con divmodu_long
+    output.push_str("# Helper: stampa di superBased in a0:a1\n");
+    output.push_str("print_long:\n");
+    output.push_str("   addi sp, sp, -32\n");
+    output.push_str("   sw   ra, 28(sp)\n");
+    output.push_str("   mv   t5, sp         # t5 = dove va la prossima cifra\n");
+    output.push_str("   li   t6, 0          # t6 = flag negativo\n");
+    output.push_str("   bgez a1, .Llong_conv_loop\n");
+    output.push_str("   li   t6, 1\n");
+    output.push_str("   # valore assoluto: il minimo negativo resta uguale, ma letto senza segno è giusto\n");
+    output.push_str("   snez t0, a0\n");
+    output.push_str("   neg  a0, a0\n");
+    output.push_str("   neg  a1, a1\n");
+    output.push_str("   sub  a1, a1, t0\n");
+    output.push_str(".Llong_conv_loop:\n");
+    output.push_str("   li   a2, 10\n");
+    output.push_str("   li   a3, 0\n");
+    output.push_str("   call divmodu_long\n");
+    output.push_str("   addi a2, a2, 48     # resto -> cifra ascii\n");
+    output.push_str("   sb   a2, 0(t5)\n");
+    output.push_str("   addi t5, t5, 1\n");
+    output.push_str("   or   t0, a0, a1\n");
+    output.push_str("   bnez t0, .Llong_conv_loop\n");
+    output.push_str("   beqz t6, .Llong_rev_start\n");
+    output.push_str("   li   t0, 45\n");
+    output.push_str("   sb   t0, 0(t5)\n");
+    output.push_str("   addi t5, t5, 1\n");
+    output.push_str(".Llong_rev_start:\n");
+    output.push_str("   mv   t1, sp\n");
+    output.push_str("   addi t2, t5, -1\n");
+    output.push_str(".Llong_rev_loop:\n");
+    output.push_str("   bge  t1, t2, .Llong_print\n");
+    output.push_str("   lb   t3, 0(t1)\n");
+    output.push_str("   lb   t4, 0(t2)\n");
+    output.push_str("   sb   t4, 0(t1)\n");
+    output.push_str("   sb   t3, 0(t2)\n");
+    output.push_str("   addi t1, t1, 1\n");
+    output.push_str("   addi t2, t2, -1\n");
+    output.push_str("   j    .Llong_rev_loop\n");
+    output.push_str(".Llong_print:\n");
+    output.push_str("   li   a0, 1\n");
+    output.push_str("   mv   a1, sp\n");
+    output.push_str("   sub  a2, t5, sp\n");
+    output.push_str("   li   a7, 64\n");
+    output.push_str("   ecall\n");
+    output.push_str("   li   a0, 1\n");
+    output.push_str("   la   a1, .Lnewline\n");
+    output.push_str("   li   a2, 1\n");
+    output.push_str("   li   a7, 64\n");
+    output.push_str("   ecall\n");
+    output.push_str("   lw   ra, 28(sp)\n");
+    output.push_str("   addi sp, sp, 32\n");
+    output.push_str("   ret\n\n");
+}
+
+// Helper per i chill (estensione F)
+fn generate_float_helpers(output: &mut String) {
+    // Stampa parte intera, punto e fino a 6 decimali arrotondati, senza gli zeri in fondo
+    // (3.14 e non 3.140000). La parte intera deve stare in un based: da 2^31 in su divido
+    // per 10 finché resta una cifra sola e stampo in notazione esponenziale (3.0e9)
+    output.push_str("# Helper: stampa di chill (bit del float in a0)\n");
+    output.push_str("print_float:\n");
+    output.push_str("   fmv.w.x ft0, a0\n");
+    output.push_str("   # nan e infiniti non hanno cifre: li stampo a parole\n");
+    output.push_str("   fclass.s t0, ft0\n");
+    output.push_str("   andi t1, t0, 0x300\n");
+    output.push_str("   la   a0, .Lnan\n");
+    output.push_str("   bnez t1, print_string\n");
+    output.push_str("   andi t1, t0, 0x80\n");
+    output.push_str("   la   a0, .Linf\n");
+    output.push_str("   bnez t1, print_string\n");
+    output.push_str("   andi t1, t0, 0x01\n");
+    output.push_str("   la   a0, .Lminus_inf\n");
+    output.push_str("   bnez t1, print_string\n");
+    output.push_str("   addi sp, sp, -32\n");
+    output.push_str("   mv   t5, sp         # t5 = dove va il prossimo carattere\n");
+    output.push_str("   fmv.w.x ft1, zero\n");
+    output.push_str("   flt.s t0, ft0, ft1\n");
+    output.push_str("   beqz t0, .Lfloat_split\n");
+    output.push_str("   li   t0, 45         # '-'\n");
+    output.push_str("   sb   t0, 0(t5)\n");
+    output.push_str("   addi t5, t5, 1\n");
+    output.push_str("   fneg.s ft0, ft0\n");
+    output.push_str(".Lfloat_split:\n");
+    output.push_str("   li   a3, 0          # a3 = esponente, resta 0 se la parte intera sta in un based\n");
+    output.push_str("   li   t0, 0x4f000000 # 2^31\n");
+    output.push_str("   fmv.w.x ft1, t0\n");
+    output.push_str("   flt.s t0, ft0, ft1\n");
+    output.push_str("   bnez t0, .Lfloat_parts\n");
+    // Costruisco 10^a3 <= x moltiplicando (esatto fino a 1e10) e divido una volta sola,
+    // dividere per 10 a ogni giro accumula errore sull'ultima cifra
+    output.push_str("   li   t0, 10\n");
+    output.push_str("   fcvt.s.w ft2, t0\n");
+    output.push_str("   fmv.s ft3, ft2\n");
+    output.push_str("   li   a3, 1\n");
+    output.push_str(".Lfloat_scale:\n");
+    output.push_str("   fmul.s ft1, ft3, ft2\n");
+    output.push_str("   fle.s t0, ft1, ft0\n");
+    output.push_str("   beqz t0, .Lfloat_scaled\n");
+    output.push_str("   fmv.s ft3, ft1\n");
+    output.push_str("   addi a3, a3, 1\n");
+    output.push_str("   j    .Lfloat_scale\n");
+    output.push_str(".Lfloat_scaled:\n");
+    output.push_str("   fdiv.s ft0, ft0, ft3\n");
+    output.push_str(".Lfloat_parts:\n");
+    output.push_str("   fcvt.w.s t0, ft0, rtz   # t0 = parte intera\n");
+    output.push_str("   fcvt.s.w ft1, t0\n");
+    output.push_str("   fsub.s ft1, ft0, ft1    # ft1 = parte decimale\n");
+    output.push_str("   li   t1, 1000000\n");
+    output.push_str("   fcvt.s.w ft2, t1\n");
+    output.push_str("   fmul.s ft1, ft1, ft2\n");
+    output.push_str("   fcvt.w.s t2, ft1, rne   # t2 = 6 decimali arrotondati\n");
+    output.push_str("   blt  t2, t1, .Lfloat_int\n");
+    output.push_str("   sub  t2, t2, t1         # 0.9999999 arrotonda a 1.0\n");
+    output.push_str("   addi t0, t0, 1\n");
+    output.push_str(".Lfloat_int:\n");
+    output.push_str("   beqz a3, .Lfloat_digits\n");
+    output.push_str("   li   t1, 10\n");
+    output.push_str("   blt  t0, t1, .Lfloat_digits\n");
+    output.push_str("   li   t0, 1          # 9.9999999e9 arrotonda a 1.0e10\n");
+    output.push_str("   addi a3, a3, 1\n");
+    output.push_str(".Lfloat_digits:\n");
+    output.push_str("   mv   t3, t5         # inizio delle cifre intere\n");
+    output.push_str("   li   t1, 10\n");
+    output.push_str(".Lfloat_int_loop:\n");
+    output.push_str("   remu t4, t0, t1\n");
+    output.push_str("   divu t0, t0, t1\n");
+    output.push_str("   addi t4, t4, 48\n");
+    output.push_str("   sb   t4, 0(t5)\n");
+    output.push_str("   addi t5, t5, 1\n");
+    output.push_str("   bnez t0, .Lfloat_int_loop\n");
+    output.push_str("   addi t4, t5, -1     # le cifre sono uscite al contrario: le giro\n");
+    output.push_str(".Lfloat_rev_loop:\n");
+    output.push_str("   bge  t3, t4, .Lfloat_frac\n");
+    output.push_str("   lb   t0, 0(t3)\n");
+    output.push_str("   lb   t6, 0(t4)\n");
+    output.push_str("   sb   t6, 0(t3)\n");
+    output.push_str("   sb   t0, 0(t4)\n");
+    output.push_str("   addi t3, t3, 1\n");
+    output.push_str("   addi t4, t4, -1\n");
+    output.push_str("   j    .Lfloat_rev_loop\n");
+    output.push_str(".Lfloat_frac:\n");
+    output.push_str("   li   t0, 46         # '.'\n");
+    output.push_str("   sb   t0, 0(t5)\n");
+    output.push_str("   addi t5, t5, 1\n");
+    output.push_str("   li   t1, 100000     # i decimali partono dalla cifra più significativa\n");
+    output.push_str("   li   t6, 10\n");
+    output.push_str(".Lfloat_frac_loop:\n");
+    output.push_str("   divu t0, t2, t1\n");
+    output.push_str("   remu t2, t2, t1\n");
+    output.push_str("   addi t0, t0, 48\n");
+    output.push_str("   sb   t0, 0(t5)\n");
+    output.push_str("   addi t5, t5, 1\n");
+    output.push_str("   divu t1, t1, t6\n");
+    output.push_str("   bnez t1, .Lfloat_frac_loop\n");
+    output.push_str("   # tolgo gli zeri in fondo, ma dopo il punto ne resta almeno una cifra\n");
+    output.push_str("   li   t1, 48\n");
+    output.push_str("   li   t2, 46\n");
+    output.push_str(".Lfloat_trim:\n");
+    output.push_str("   lb   t0, -1(t5)\n");
+    output.push_str("   bne  t0, t1, .Lfloat_exponent\n");
+    output.push_str("   lb   t0, -2(t5)\n");
+    output.push_str("   beq  t0, t2, .Lfloat_exponent\n");
+    output.push_str("   addi t5, t5, -1\n");
+    output.push_str("   j    .Lfloat_trim\n");
+    output.push_str(".Lfloat_exponent:\n");
+    output.push_str("   beqz a3, .Lfloat_print\n");
+    output.push_str("   li   t0, 101        # 'e', poi l'esponente (al massimo 38, due cifre)\n");
+    output.push_str("   sb   t0, 0(t5)\n");
+    output.push_str("   addi t5, t5, 1\n");
+    output.push_str("   li   t1, 10\n");
+    output.push_str("   blt  a3, t1, .Lfloat_exponent_units\n");
+    output.push_str("   divu t0, a3, t1\n");
+    output.push_str("   addi t0, t0, 48\n");
+    output.push_str("   sb   t0, 0(t5)\n");
+    output.push_str("   addi t5, t5, 1\n");
+    output.push_str("   remu a3, a3, t1\n");
+    output.push_str(".Lfloat_exponent_units:\n");
+    output.push_str("   addi t0, a3, 48\n");
+    output.push_str("   sb   t0, 0(t5)\n");
+    output.push_str("   addi t5, t5, 1\n");
+    output.push_str(".Lfloat_print:\n");
+    output.push_str("   li   a0, 1\n");
+    output.push_str("   mv   a1, sp\n");
+    output.push_str("   sub  a2, t5, sp\n");
+    output.push_str("   li   a7, 64\n");
+    output.push_str("   ecall\n");
+    output.push_str("   li   a0, 1\n");
+    output.push_str("   la   a1, .Lnewline\n");
+    output.push_str("   li   a2, 1\n");
+    output.push_str("   li   a7, 64\n");
+    output.push_str("   ecall\n");
+    output.push_str("   addi sp, sp, 32\n");
+    output.push_str("   ret\n\n");
+}
+
+// Helper delle builtin di input (readBased, readChad, readVibes): leggono da stdin
+// un byte alla volta con la syscall read (63), così non resta niente in un buffer
+// nascosto e le letture diverse si possono mescolare. Usano solo t0..t6
+fn generate_input_helpers(output: &mut String) {
+    // Salta spazi e a capo, poi legge un '-' opzionale e le cifre. Il primo carattere
+    // dopo il numero viene consumato (di solito è lo spazio o il '\n' che lo chiude)
+    output.push_str("# Helper: legge un based da stdin, risultato in a0\n");
+    output.push_str("read_int:\n");
+    output.push_str("   addi sp, sp, -16\n");
+    output.push_str("   li   t0, 0          # valore\n");
+    output.push_str("   li   t1, 1          # segno\n");
+    output.push_str("   li   t4, 0          # diventa 1 quando il numero è iniziato\n");
+    output.push_str(".Lread_int_loop:\n");
+    output.push_str("   li   a0, 0          # stdin\n");
+    output.push_str("   mv   a1, sp\n");
+    output.push_str("   li   a2, 1\n");
+    output.push_str("   li   a7, 63         # syscall read\n");
+    output.push_str("   ecall\n");
+    output.push_str("   blez a0, .Lread_int_done   # fine input\n");
+    output.push_str("   lbu  t2, 0(sp)\n");
+    output.push_str("   addi t3, t2, -48\n");
+    output.push_str("   li   t5, 10\n");
+    output.push_str("   bltu t3, t5, .Lread_int_digit\n");
+    output.push_str("   bnez t4, .Lread_int_done   # il numero è finito\n");
+    output.push_str("   li   t5, 45\n");
+    output.push_str("   beq  t2, t5, .Lread_int_minus\n");
+    output.push_str("   li   t5, 32\n");
+    output.push_str("   beq  t2, t5, .Lread_int_loop   # spazio\n");
+    output.push_str("   addi t3, t2, -9\n");
+    output.push_str("   li   t5, 5\n");
+    output.push_str("   bltu t3, t5, .Lread_int_loop   # \\t \\n \\v \\f \\r\n");
+    output.push_str("   j    .Lread_int_done           # non è un numero: 0\n");
+    output.push_str(".Lread_int_minus:\n");
+    output.push_str("   li   t1, -1\n");
+    output.push_str("   li   t4, 1\n");
+    output.push_str("   j    .Lread_int_loop\n");
+    output.push_str(".Lread_int_digit:\n");
+    output.push_str("   li   t5, 10\n");
+    output.push_str("   mul  t0, t0, t5\n");
+    output.push_str("   add  t0, t0, t3\n");
+    output.push_str("   li   t4, 1\n");
+    output.push_str("   j    .Lread_int_loop\n");
+    output.push_str(".Lread_int_done:\n");
+    output.push_str("   mul  a0, t0, t1\n");
+    output.push_str("   addi sp, sp, 16\n");
+    output.push_str("   ret\n\n");
+
+    output.push_str("# Helper: legge un chad da stdin, risultato in a0 (0 a fine input)\n");
+    output.push_str("read_char:\n");
+    output.push_str("   addi sp, sp, -16\n");
+    output.push_str("   sb   zero, 0(sp)    # se la read non legge niente resta 0\n");
+    output.push_str("   li   a0, 0          # stdin\n");
+    output.push_str("   mv   a1, sp\n");
+    output.push_str("   li   a2, 1\n");
+    output.push_str("   li   a7, 63         # syscall read\n");
+    output.push_str("   ecall\n");
+    output.push_str("   lbu  a0, 0(sp)\n");
+    output.push_str("   addi sp, sp, 16\n");
+    output.push_str("   ret\n\n");
+
+    // Legge nel buffer al massimo size - 1 caratteri, fermandosi al '\n' (che non viene
+    // salvato) o a fine input, e chiude la stringa con '\0'. Se la riga è più lunga
+    // il resto rimane per la lettura successiva
+    output.push_str("# Helper: legge una riga in a0 (buffer) di a1 (size) byte, ritorna il buffer in a0\n");
+    output.push_str("read_line:\n");
+    output.push_str("   mv   t0, a0         # buffer\n");
+    output.push_str("   addi t1, a1, -1     # caratteri che ci stanno, l'ultimo byte è per '\\0'\n");
+    output.push_str("   bltz t1, .Lread_line_end   # size <= 0: non c'è spazio neanche per '\\0'\n");
+    output.push_str("   li   t2, 0          # caratteri letti\n");
+    output.push_str(".Lread_line_loop:\n");
+    output.push_str("   bge  t2, t1, .Lread_line_done\n");
+    output.push_str("   li   a0, 0          # stdin\n");
+    output.push_str("   add  a1, t0, t2     # leggo direttamente nel buffer\n");
+    output.push_str("   li   a2, 1\n");
+    output.push_str("   li   a7, 63         # syscall read\n");
+    output.push_str("   ecall\n");
+    output.push_str("   blez a0, .Lread_line_done  # fine input\n");
+    output.push_str("   add  t3, t0, t2\n");
+    output.push_str("   lbu  t3, 0(t3)\n");
+    output.push_str("   li   t4, 10\n");
+    output.push_str("   beq  t3, t4, .Lread_line_done  # '\\n' chiude la riga\n");
+    output.push_str("   addi t2, t2, 1\n");
+    output.push_str("   j    .Lread_line_loop\n");
+    output.push_str(".Lread_line_done:\n");
+    output.push_str("   add  t3, t0, t2\n");
+    output.push_str("   sb   zero, 0(t3)\n");
+    output.push_str(".Lread_line_end:\n");
+    output.push_str("   mv   a0, t0\n");
+    output.push_str("   ret\n\n");
+}
+
+// Helper delle builtin alloc e free: un allocatore first fit sopra la syscall brk (214).
+// Ogni blocco ha un header di 8 byte (dimensione del blocco, header compreso, e next quando
+// è libero) e i dati subito dopo, allineati a 8 come serve ai superBased. free rimette il
+// blocco in testa alla lista dei liberi, alloc la scorre prima di allargare lo heap con brk
+fn generate_heap_helpers(output: &mut String) {
+    output.push_str("# Helper: alloca a0 byte sullo heap, indirizzo in a0 (0 se a0 <= 0 o se la memoria è finita)\n");
+    output.push_str("heap_alloc:\n");
+    output.push_str("   blez a0, .Lalloc_fail\n");
+    output.push_str("   addi t0, a0, 15\n");
+    output.push_str("   andi t0, t0, -8     # t0 = blocco: dati arrotondati a 8 + header\n");
+    output.push_str("   la   t1, .Lfree_list   # t1 = dove è salvato il puntatore al blocco corrente\n");
+    output.push_str(".Lalloc_search:\n");
+    output.push_str("   lw   t2, 0(t1)      # t2 = blocco libero corrente\n");
+    output.push_str("   beqz t2, .Lalloc_grow\n");
+    output.push_str("   lw   t3, 0(t2)      # t3 = sua dimensione\n");
+    output.push_str("   bgeu t3, t0, .Lalloc_found\n");
+    output.push_str("   addi t1, t2, 4      # passo al next\n");
+    output.push_str("   j    .Lalloc_search\n");
+    output.push_str(".Lalloc_found:\n");
+    output.push_str("   sub  t4, t3, t0     # quanto avanza\n");
+    output.push_str("   li   t5, 16\n");
+    output.push_str("   bltu t4, t5, .Lalloc_take   # troppo poco per un altro blocco: lo do tutto\n");
+    output.push_str("   add  t5, t2, t0     # la parte che avanza resta libera al posto del blocco\n");
+    output.push_str("   sw   t4, 0(t5)\n");
+    output.push_str("   lw   t6, 4(t2)\n");
+    output.push_str("   sw   t6, 4(t5)\n");
+    output.push_str("   sw   t5, 0(t1)\n");
+    output.push_str("   sw   t0, 0(t2)\n");
+    output.push_str("   addi a0, t2, 8\n");
+    output.push_str("   ret\n");
+    output.push_str(".Lalloc_take:\n");
+    output.push_str("   lw   t6, 4(t2)\n");
+    output.push_str("   sw   t6, 0(t1)      # tolgo il blocco dalla lista\n");
+    output.push_str("   addi a0, t2, 8\n");
+    output.push_str("   ret\n");
+    output.push_str(".Lalloc_grow:\n");
+    output.push_str("   # nessun blocco libero abbastanza grande: allargo lo heap\n");
+    output.push_str("   la   t1, .Lheap_end\n");
+    output.push_str("   lw   t2, 0(t1)      # t2 = fine dello heap\n");
+    output.push_str("   bnez t2, .Lalloc_brk\n");
+    output.push_str("   li   a0, 0\n");
+    output.push_str("   li   a7, 214        # brk(0): la prima volta chiedo dove inizia lo heap\n");
+    output.push_str("   ecall\n");
+    output.push_str("   addi t2, a0, 7\n");
+    output.push_str("   andi t2, t2, -8\n");
+    output.push_str(".Lalloc_brk:\n");
+    output.push_str("   add  t3, t2, t0     # t3 = nuova fine\n");
+    output.push_str("   mv   a0, t3\n");
+    output.push_str("   li   a7, 214        # syscall brk\n");
+    output.push_str("   ecall\n");
+    output.push_str("   bltu a0, t3, .Lalloc_fail   # il kernel non ci ha dato la memoria\n");
+    output.push_str("   sw   t3, 0(t1)\n");
+    output.push_str("   sw   t0, 0(t2)      # header con la dimensione\n");
+    output.push_str("   addi a0, t2, 8\n");
+    output.push_str("   ret\n");
+    output.push_str(".Lalloc_fail:\n");
+    output.push_str("   li   a0, 0          # ghosted\n");
+    output.push_str("   ret\n\n");
+
+    output.push_str("# Helper: libera il blocco in a0 restituito da heap_alloc\n");
+    output.push_str("heap_free:\n");
+    output.push_str("   beqz a0, .Lfree_done   # free(ghosted) non fa niente\n");
+    output.push_str("   addi t0, a0, -8     # header del blocco\n");
+    output.push_str("   la   t1, .Lfree_list\n");
+    output.push_str("   lw   t2, 0(t1)\n");
+    output.push_str("   sw   t2, 4(t0)      # next = vecchia testa della lista\n");
+    output.push_str("   sw   t0, 0(t1)\n");
+    output.push_str(".Lfree_done:\n");
+    output.push_str("   ret\n\n");
+}
+
+// Helper delle vibes: lunghezza, concatenazione, confronto e conversioni con i based.
+// Le stringhe nuove (str_concat, int_to_str) vengono allocate con heap_alloc
+fn generate_string_helpers(output: &mut String) {
+    output.push_str("# Helper: lunghezza della stringa in a0, risultato in a0\n");
+    output.push_str("str_len:\n");
+    output.push_str("   mv   t0, a0\n");
+    output.push_str(".Lstr_len_loop:\n");
+    output.push_str("   lbu  t1, 0(t0)\n");
+    output.push_str("   beqz t1, .Lstr_len_done\n");
+    output.push_str("   addi t0, t0, 1\n");
+    output.push_str("   j    .Lstr_len_loop\n");
+    output.push_str(".Lstr_len_done:\n");
+    output.push_str("   sub  a0, t0, a0\n");
+    output.push_str("   ret\n\n");
+
+    output.push_str("# Helper: concatena a0 e a1 in una stringa nuova sullo heap, indirizzo in a0\n");
+    output.push_str("str_concat:\n");
+    output.push_str("   addi sp, sp, -16\n");
+    output.push_str("   sw   ra, 12(sp)\n");
+    output.push_str("   sw   a0, 0(sp)      # prima stringa\n");
+    output.push_str("   sw   a1, 4(sp)      # seconda stringa\n");
+    output.push_str("   call str_len\n");
+    output.push_str("   sw   a0, 8(sp)\n");
+    output.push_str("   lw   a0, 4(sp)\n");
+    output.push_str("   call str_len\n");
+    output.push_str("   lw   t0, 8(sp)\n");
+    output.push_str("   add  a0, a0, t0\n");
+    output.push_str("   addi a0, a0, 1      # più lo '\\0'\n");
+    output.push_str("   call heap_alloc\n");
+    output.push_str("   beqz a0, .Lconcat_done   # memoria finita\n");
+    output.push_str("   mv   t0, a0         # dove scrivo\n");
+    output.push_str("   lw   t1, 0(sp)\n");
+    output.push_str(".Lconcat_first:\n");
+    output.push_str("   lbu  t2, 0(t1)\n");
+    output.push_str("   beqz t2, .Lconcat_second_start\n");
+    output.push_str("   sb   t2, 0(t0)\n");
+    output.push_str("   addi t0, t0, 1\n");
+    output.push_str("   addi t1, t1, 1\n");
+    output.push_str("   j    .Lconcat_first\n");
+    output.push_str(".Lconcat_second_start:\n");
+    output.push_str("   lw   t1, 4(sp)\n");
+    output.push_str(".Lconcat_second:\n");
+    output.push_str("   lbu  t2, 0(t1)\n");
+    output.push_str("   sb   t2, 0(t0)      # copio anche lo '\\0' finale\n");
+    output.push_str("   addi t0, t0, 1\n");
+    output.push_str("   addi t1, t1, 1\n");
+    output.push_str("   bnez t2, .Lconcat_second\n");
+    output.push_str(".Lconcat_done:\n");
+    output.push_str("   lw   ra, 12(sp)\n");
+    output.push_str("   addi sp, sp, 16\n");
+    output.push_str("   ret\n\n");
+
+    // Come strcmp: la differenza tra i primi byte diversi, 0 se le stringhe sono uguali
+    output.push_str("# Helper: confronta le stringhe in a0 e a1, risultato in a0 (<0, 0, >0)\n");
+    output.push_str("str_compare:\n");
+    output.push_str("   lbu  t0, 0(a0)\n");
+    output.push_str("   lbu  t1, 0(a1)\n");
+    output.push_str("   bne  t0, t1, .Lcompare_done\n");
+    output.push_str("   beqz t0, .Lcompare_done   # finite insieme: uguali\n");
+    output.push_str("   addi a0, a0, 1\n");
+    output.push_str("   addi a1, a1, 1\n");
+    output.push_str("   j    str_compare\n");
+    output.push_str(".Lcompare_done:\n");
+    output.push_str("   sub  a0, t0, t1\n");
+    output.push_str("   ret\n\n");
+
+    // Stesse cifre di print_int, ma scritte in una stringa sullo heap invece che su stdout
+    output.push_str("# Helper: converte il based in a0 in una stringa nuova sullo heap, indirizzo in a0\n");
+    output.push_str("int_to_str:\n");
+    output.push_str("   addi sp, sp, -16\n");
+    output.push_str("   sw   ra, 12(sp)\n");
+    output.push_str("   sw   a0, 0(sp)\n");
+    output.push_str("   li   a0, 12         # \"-2147483648\" e lo '\\0'\n");
+    output.push_str("   call heap_alloc\n");
+    output.push_str("   beqz a0, .Litoa_done   # memoria finita\n");
+    output.push_str("   lw   t2, 0(sp)      # numero da convertire\n");
+    output.push_str("   mv   t0, a0         # dove scrivo la prossima cifra\n");
+    output.push_str("   li   t1, 10\n");
+    output.push_str("   li   t3, 0\n");
+    output.push_str("   bgez t2, .Litoa_loop\n");
+    output.push_str("   li   t3, 1\n");
+    output.push_str("   neg  t2, t2\n");
+    output.push_str(".Litoa_loop:\n");
+    output.push_str("   remu t4, t2, t1\n");
+    output.push_str("   divu t2, t2, t1\n");
+    output.push_str("   addi t4, t4, 48\n");
+    output.push_str("   sb   t4, 0(t0)\n");
+    output.push_str("   addi t0, t0, 1\n");
+    output.push_str("   bnez t2, .Litoa_loop\n");
+    output.push_str("   beqz t3, .Litoa_end\n");
+    output.push_str("   li   t4, 45\n");
+    output.push_str("   sb   t4, 0(t0)\n");
+    output.push_str("   addi t0, t0, 1\n");
+    output.push_str(".Litoa_end:\n");
+    output.push_str("   sb   zero, 0(t0)\n");
+    output.push_str("   # le cifre sono al contrario: le giro\n");
+    output.push_str("   mv   t1, a0\n");
+    output.push_str("   addi t2, t0, -1\n");
+    output.push_str(".Litoa_reverse:\n");
+    output.push_str("   bge  t1, t2, .Litoa_done\n");
+    output.push_str("   lbu  t3, 0(t1)\n");
+    output.push_str("   lbu  t4, 0(t2)\n");
+    output.push_str("   sb   t4, 0(t1)\n");
+    output.push_str("   sb   t3, 0(t2)\n");
+    output.push_str("   addi t1, t1, 1\n");
+    output.push_str("   addi t2, t2, -1\n");
+    output.push_str("   j    .Litoa_reverse\n");
+    output.push_str(".Litoa_done:\n");
+    output.push_str("   lw   ra, 12(sp)\n");
+    output.push_str("   addi sp, sp, 16\n");
+    output.push_str("   ret\n\n");
+
+    // Come read_int: spazi iniziali, '-' opzionale, poi le cifre fino al primo carattere
+    // che non lo è. Se non c'è un numero il risultato è 0
+    output.push_str("# Helper: converte la stringa in a0 in un based, risultato in a0\n");
+    output.push_str("str_to_int:\n");
+    output.push_str("   li   t0, 0          # valore\n");
+    output.push_str("   li   t1, 1          # segno\n");
+    output.push_str(".Latoi_space:\n");
+    output.push_str("   lbu  t2, 0(a0)\n");
+    output.push_str("   li   t5, 32\n");
+    output.push_str("   beq  t2, t5, .Latoi_next_space\n");
+    output.push_str("   addi t3, t2, -9\n");
+    output.push_str("   li   t5, 5\n");
+    output.push_str("   bgeu t3, t5, .Latoi_sign   # non è \\t \\n \\v \\f \\r\n");
+    output.push_str(".Latoi_next_space:\n");
+    output.push_str("   addi a0, a0, 1\n");
+    output.push_str("   j    .Latoi_space\n");
+    output.push_str(".Latoi_sign:\n");
+    output.push_str("   li   t5, 45\n");
+    output.push_str("   bne  t2, t5, .Latoi_digits\n");
+    output.push_str("   li   t1, -1\n");
+    output.push_str("   addi a0, a0, 1\n");
+    output.push_str(".Latoi_digits:\n");
+    output.push_str("   lbu  t2, 0(a0)\n");
+    output.push_str("   addi t3, t2, -48\n");
+    output.push_str("   li   t5, 10\n");
+    output.push_str("   bgeu t3, t5, .Latoi_done\n");
+    output.push_str("   mul  t0, t0, t5\n");
+    output.push_str("   add  t0, t0, t3\n");
+    output.push_str("   addi a0, a0, 1\n");
+    output.push_str("   j    .Latoi_digits\n");
+    output.push_str(".Latoi_done:\n");
+    output.push_str("   mul  a0, t0, t1\n");
     output.push_str("   ret\n\n");
 }
 
@@ -530,7 +1923,7 @@ fn generate_helpers(output: &mut String) {
 
 | Riga  | Codice               | Cosa fa                                |
 |-------|----------------------|----------------------------------------|
-| 1     | addi sp, sp, -20     | Alloca 20 byte sullo stack per buffer  |
+| 1     | addi sp, sp, -32     | Alloca 32 byte sullo stack per buffer  |
 | 2     | mv t0, sp            | t0 = puntatore al buffer               |
 | 3     | li t1, 10            | t1 = 10 (divisore)                     |
 | 4     | mv t2, a0            | t2 = numero da convertire              |
@@ -543,7 +1936,7 @@ fn generate_helpers(output: &mut String) {
 | 20-30 | Inverti buffer       | Scambia byte dall'inizio alla fine     |
 | 31-35 | write()              | Stampa il numero                       |
 | 36-40 | write() newline      | Stampa '\n'                            |
-| 41    | addi sp, sp, 20      | Dealloca buffer                        |
+| 41    | addi sp, sp, 32      | Dealloca buffer                        |
 | 42    | ret                  | Ritorna                                |*/
 
 fn generate_entry_point(output: &mut String) {
@@ -555,17 +1948,141 @@ fn generate_entry_point(output: &mut String) {
     output.push_str("ecall\n\n");
 }
 
-fn generate_data_section(output: &mut String, ctx: &mut CodeGenContext, ast: &Program) -> Result<(), String> {
+fn generate_data_section(output: &mut String, ctx: &mut CodeGenContext, globals: &[Global]) -> Result<(), Diagnostic> {
+    // Prima le globali, perché una vibes inizializzata aggiunge la sua stringa a quelle da scrivere.
+    // Le costanti vanno in .rodata (scriverci fa crashare), le variabili a zero in .bss
+    // (il loader le azzera senza occupare spazio nel file), le altre in .data
+    let mut data = String::new();
+    let mut rodata = String::new();
+    let mut bss = String::new();
+    for global in globals {
+        let decl = resolved_decl(global.decl, &global.name, global.span)?;
+        let label = ctx.globals.get(&decl).cloned()
+            .ok_or_else(|| Diagnostic::error(global.span, format!("Global '{}' not found", global.name)))?;
+        let align = ctx.type_align(&global.var_type);
+        let (directive, is_zero) = global_initializer(ctx, global)?;
+
+        if global.constant {
+            rodata.push_str(&format!(".balign {}\n{}: {}\n", align, label, directive));
+        } else if is_zero {
+            bss.push_str(&format!(".balign {}\n{}: .zero {}\n", align, label, ctx.type_size(&global.var_type)));
+        } else {
+            data.push_str(&format!(".balign {}\n{}: {}\n", align, label, directive));
+        }
+    }
+
     // Genera sempre la sezione .data perché le funzioni print_int e print_string usano .Lnewline
     output.push_str("\n.data\n");
 
     // Stringhe letterali (se presenti)
     for (i, s) in ctx.string_literals.iter().enumerate() {
-        output.push_str(&format!("str_{}: .asciz \"{}\"\n", i, s));
+        output.push_str(&format!("str_{}: .asciz \"{}\"\n", i, asm_string(s)));
     }
 
     // Label per il newline (sempre necessaria per print_int e print_string)
-    output.push_str(".Lnewline: .asciz \"\n\" \n");
+    output.push_str(".Lnewline: .asciz \"\\n\"\n");
+
+    // Testo stampato da print_bool
+    output.push_str(".Lfr: .asciz \"fr\"\n");
+    output.push_str(".Lcap: .asciz \"cap\"\n");
+
+    // Testo stampato da print_float per i valori senza cifre
+    output.push_str(".Lnan: .asciz \"nan\"\n");
+    output.push_str(".Linf: .asciz \"inf\"\n");
+    output.push_str(".Lminus_inf: .asciz \"-inf\"\n");
+
+    // Stato dell'allocatore: fine dello heap (0 finché heap_alloc non chiede a brk dove inizia)
+    // e testa della lista dei blocchi liberi
+    output.push_str(".balign 4\n");
+    output.push_str(".Lheap_end: .word 0\n");
+    output.push_str(".Lfree_list: .word 0\n");
+
+    // Variabili globali inizializzate
+    output.push_str(&data);
+
+    if !rodata.is_empty() {
+        output.push_str("\n.section .rodata\n");
+        output.push_str(&rodata);
+    }
+
+    if !bss.is_empty() {
+        output.push_str("\n.bss\n");
+        output.push_str(&bss);
+    }
 
     Ok(())
+}
+
+// Riscrive il contenuto di una stringa per .asciz: virgolette e backslash con il loro escape,
+// il resto dell'ASCII stampabile così com'è e ogni altro byte (a capo, tab, UTF-8, \xff...) in ottale,
+// che l'assembler legge sempre come un byte solo
+fn asm_string(s: &[u8]) -> String {
+    let mut escaped = String::new();
+    for &byte in s {
+        match byte {
+            b'"' => escaped.push_str("\\\""),
+            b'\\' => escaped.push_str("\\\\"),
+            b' '..=b'~' => escaped.push(byte as char),
+            _ => escaped.push_str(&format!("\\{:03o}", byte))
+        }
+    }
+    escaped
+}
+
+// Direttiva con il valore iniziale di una globale, e se quel valore è tutto zero.
+// Che sia un letterale del tipo giusto l'ha già controllato il semantic
+fn global_initializer(ctx: &mut CodeGenContext, global: &Global) -> Result<(String, bool), Diagnostic> {
+    let value = &global.value;
+    let not_literal = || Diagnostic::error(value.span, format!("Initial value of global '{}' is not a literal", global.name));
+
+    let initializer = match &global.var_type {
+        // la globale contiene l'indirizzo della stringa, che finisce in .data con le altre
+        Type::Vibes => match &value.kind {
+            ExpressionKind::StringLit(s) => (format!(".word {}", ctx.add_string_literal(s.clone())), false),
+            _ => return Err(not_literal())
+        },
+        Type::Chill => {
+            let f = literal_float(value).ok_or_else(not_literal)?;
+            let bits = (f as f32).to_bits();
+            (format!(".word {}   # {}", bits as i32, f), bits == 0)
+        },
+        // parte bassa per prima, come nei frame
+        Type::SuperBased => {
+            let n = literal_int(value).ok_or_else(not_literal)?;
+            (format!(".word {}, {}", n as i32, (n >> 32) as i32), n == 0)
+        },
+        Type::Chad | Type::NoCap => {
+            let n = literal_int(value).ok_or_else(not_literal)?;
+            (format!(".byte {}", n as u8), n == 0)
+        },
+        // based e puntatori (che all'inizio possono valere solo ghosted)
+        _ => {
+            let n = literal_int(value).ok_or_else(not_literal)?;
+            (format!(".word {}", n as i32), n == 0)
+        }
+    };
+
+    Ok(initializer)
+}
+
+// Valore di un letterale intero, chad, nocap o ghosted (anche con il meno davanti)
+fn literal_int(expr: &Expression) -> Option<i64> {
+    match &expr.kind {
+        ExpressionKind::Integer(n) => Some(*n as i64),
+        ExpressionKind::Long(n) => Some(*n),
+        ExpressionKind::CharLit(c) => Some(*c as i64),
+        ExpressionKind::Bool(b) => Some(*b as i64),
+        ExpressionKind::Null => Some(0),
+        ExpressionKind::UnaryOp { op: UnaryOp::Neg, operand } => literal_int(operand).map(i64::wrapping_neg),
+        _ => None
+    }
+}
+
+// Un chill si può inizializzare anche con un based (chill x slay 5;)
+fn literal_float(expr: &Expression) -> Option<f64> {
+    match &expr.kind {
+        ExpressionKind::Float(f) => Some(*f),
+        ExpressionKind::UnaryOp { op: UnaryOp::Neg, operand } => literal_float(operand).map(|f| -f),
+        _ => literal_int(expr).map(|n| n as f64)
+    }
 }
\ No newline at end of file
@@ -2,33 +2,35 @@ use crate::lexer::{chunker, tokenizer};
 use crate::parser::{parse};
 use crate::semantic::{analyze_program};
 use crate::codegen::generate_riscv;
+use crate::error::Diagnostic;
 use std::fs;
 
-pub fn compile(code: &str, output_path: &str) -> Result<(), String> {
+// In caso di errore ritorna tutti i diagnostic trovati dalla fase che è fallita
+pub fn compile(code: &str, output_path: &str) -> Result<(), Vec<Diagnostic>> {
     // 1. Prima chunker: divide il codice in pezzi
     let chunks = chunker(code);
 
     // 2. Poi tokenizer: trasforma i chunk in token
-    let tokens = tokenizer(chunks);
+    let tokens = tokenizer(chunks)?;
 
     println!("\n=== Tokenizzazione completata ===");
     println!("Totale token: {}", tokens.len());
 
-    let ast = parse(tokens)?;
+    let mut ast = parse(tokens)?;
     println!("\n===== Stampo AST =====");
     println!("{:#?}", ast);
 
     // Analisi semantica
-    analyze_program(&ast)?;
+    analyze_program(&mut ast)?;
 
     // Generazione codice RISC-V
-    let riscv_code = generate_riscv(&ast)?;
+    let riscv_code = generate_riscv(&ast).map_err(|err| vec![err])?;
 
     // Salva il codice assembly in un file .s
     fs::write(output_path, riscv_code)
-        .map_err(|e| format!("Errore nella scrittura del file '{}': {}", output_path, e))?;
+        .map_err(|e| vec![Diagnostic::global(format!("Errore nella scrittura del file '{}': {}", output_path, e))])?;
 
     println!("\n✓ Codice RISC-V generato in: {}", output_path);
 
     Ok(())
-}
\ No newline at end of file
+}
@@ -0,0 +1,134 @@
+use crate::lexer::Span;
+
+// Span secondario con la sua spiegazione (es. "previous declaration here")
+#[derive(Debug, Clone)]
+pub struct Label {
+    pub span: Span,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NoteKind {
+    Note,   // informazione in più sull'errore
+    Help,   // suggerimento su come sistemarlo
+}
+
+// Riga "= note: ..." / "= help: ..." in fondo al messaggio
+#[derive(Debug, Clone)]
+pub struct Note {
+    pub kind: NoteKind,
+    pub message: String,
+}
+
+// Errore prodotto da una qualsiasi fase del compilatore
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    pub message: String,
+    pub primary: Option<Span>,   // None per errori che non dipendono dal sorgente (es. I/O)
+    pub secondary: Vec<Label>,
+    pub notes: Vec<Note>,
+}
+
+impl Diagnostic {
+    pub fn error(span: Span, message: impl Into<String>) -> Self {
+        Self::new(Some(span), message.into())
+    }
+
+    // Errore senza posizione nel sorgente
+    pub fn global(message: impl Into<String>) -> Self {
+        Self::new(None, message.into())
+    }
+
+    fn new(primary: Option<Span>, message: String) -> Self {
+        Self {
+            message,
+            primary,
+            secondary: Vec::new(),
+            notes: Vec::new(),
+        }
+    }
+
+    pub fn with_label(mut self, span: Span, message: impl Into<String>) -> Self {
+        self.secondary.push(Label { span, message: message.into() });
+        self
+    }
+
+    pub fn with_note(mut self, note: impl Into<String>) -> Self {
+        self.notes.push(Note { kind: NoteKind::Note, message: note.into() });
+        self
+    }
+
+    pub fn with_help(mut self, help: impl Into<String>) -> Self {
+        self.notes.push(Note { kind: NoteKind::Help, message: help.into() });
+        self
+    }
+
+    // Stampa stile rustc:
+    //
+    // error: Expected ';' after variable value
+    //  --> program.sgm:2:19
+    //   |
+    // 2 |     based x slay 5
+    //   |                   ^
+    //   = help: ...
+    pub fn render(&self, file_name: &str, source: &str) -> String {
+        let mut out = format!("error: {}\n", self.message);
+
+        let lines: Vec<&str> = source.lines().collect();
+
+        // larghezza della colonna dei numeri di riga
+        let max_line = self.primary.iter()
+            .chain(self.secondary.iter().map(|l| &l.span))
+            .map(|s| s.line)
+            .max()
+            .unwrap_or(0);
+        let width = max_line.to_string().len();
+        let pad = " ".repeat(width);
+
+        if let Some(span) = self.primary {
+            out.push_str(&format!("{}--> {}:{}\n", pad, file_name, span));
+            out.push_str(&format!("{} |\n", pad));
+            render_snippet(&mut out, &lines, span, '^', None, width);
+        }
+
+        for label in &self.secondary {
+            render_snippet(&mut out, &lines, label.span, '-', Some(&label.message), width);
+        }
+
+        for note in &self.notes {
+            let kind = match note.kind {
+                NoteKind::Note => "note",
+                NoteKind::Help => "help",
+            };
+            out.push_str(&format!("{} = {}: {}\n", pad, kind, note.message));
+        }
+
+        out
+    }
+}
+
+// Stampa la riga del sorgente e sotto la sottolineatura dello span
+fn render_snippet(out: &mut String, lines: &[&str], span: Span, marker: char, label: Option<&str>, width: usize) {
+    let Some(text) = lines.get(span.line.wrapping_sub(1)) else {
+        return;
+    };
+
+    let line_len = text.chars().count();
+    let start = span.column.saturating_sub(1);
+
+    // lo span non può uscire dalla riga (es. stringhe su più righe), ma almeno un carattere lo segno
+    let len = span.len.min(line_len.saturating_sub(start)).max(1);
+
+    // i tab vanno ricopiati così la sottolineatura resta allineata
+    let indent: String = text.chars()
+        .take(start)
+        .map(|c| if c == '\t' { '\t' } else { ' ' })
+        .collect();
+
+    out.push_str(&format!("{:>width$} | {}\n", span.line, text, width = width));
+    out.push_str(&format!("{:>width$} | {}{}", "", indent, marker.to_string().repeat(len), width = width));
+    if let Some(label) = label {
+        out.push_str(&format!(" {}", label));
+    }
+    out.push('\n');
+}
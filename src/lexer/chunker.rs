@@ -1,4 +1,31 @@
-pub fn chunker(code: &str) -> Vec<String> {
+use crate::lexer::span::Span;
+
+// Un pezzo di codice insieme alla posizione in cui inizia nel sorgente
+#[derive(Debug, Clone)]
+pub struct Chunk {
+    pub text: String,
+    pub span: Span,
+}
+
+// Salva il chunk corrente (se non vuoto) con la posizione da cui era iniziato
+fn flush(chunks: &mut Vec<Chunk>, chunk: &mut String, start: (usize, usize)) {
+    if !chunk.is_empty() {
+        let len = chunk.chars().count();
+        chunks.push(Chunk { text: std::mem::take(chunk), span: Span::new(start.0, start.1, len) });
+    }
+}
+
+// true se a partire da chars[i] c'è esattamente `pattern`
+fn starts_with(chars: &[char], i: usize, pattern: &str) -> bool {
+    pattern.chars().enumerate().all(|(k, p)| chars.get(i + k) == Some(&p))
+}
+
+// 'ngl' è un commento solo se è una parola a sé (non l'inizio di un identificatore tipo 'nglx')
+fn is_ngl(chars: &[char], i: usize) -> bool {
+    starts_with(chars, i, "ngl") && chars.get(i + 3).is_none_or(|c| c.is_whitespace())
+}
+
+pub fn chunker(code: &str) -> Vec<Chunk> {
     let mut chunks = Vec::new();
     let mut chunk: String = String::new();
     let mut in_string = false;
@@ -7,16 +34,73 @@ pub fn chunker(code: &str) -> Vec<String> {
     let chars: Vec<char> = code.chars().collect();
     let mut i = 0;
 
+    // riga e colonna di ogni carattere, così ogni chunk sa dove inizia
+    let mut positions = Vec::with_capacity(chars.len());
+    let (mut line, mut column) = (1, 1);
+    for c in &chars {
+        positions.push((line, column));
+        if *c == '\n' {
+            line += 1;
+            column = 1;
+        } else {
+            column += 1;
+        }
+    }
+
+    // posizione del primo carattere del chunk corrente
+    let mut start = (1, 1);
+
     while i < chars.len() {
         let c = chars[i];
 
+        // Commenti: '//' e 'ngl' fino a fine riga, '/* ... */' anche annidati.
+        // Le posizioni sono già calcolate, quindi saltarli non sballa le righe
+        if !in_string && !in_char {
+            if starts_with(&chars, i, "//") || (chunk.is_empty() && is_ngl(&chars, i)) {
+                flush(&mut chunks, &mut chunk, start);
+                while i < chars.len() && chars[i] != '\n' {
+                    i += 1;
+                }
+                continue;
+            }
+
+            if starts_with(&chars, i, "/*") {
+                flush(&mut chunks, &mut chunk, start);
+                let open = i;
+                let mut depth = 0;
+                while i < chars.len() {
+                    if starts_with(&chars, i, "/*") {
+                        depth += 1;
+                        i += 2;
+                    } else if starts_with(&chars, i, "*/") {
+                        depth -= 1;
+                        i += 2;
+                        if depth == 0 {
+                            break;
+                        }
+                    } else {
+                        i += 1;
+                    }
+                }
+
+                // commento mai chiuso: lascio un chunk '/*' così il tokenizer lo segnala
+                if depth > 0 {
+                    let (line, column) = positions[open];
+                    chunks.push(Chunk { text: "/*".to_string(), span: Span::new(line, column, 2) });
+                }
+                continue;
+            }
+        }
+
         // Gestione virgolette doppie (stringhe)
         if c == '"' && !in_char {
+            if chunk.is_empty() {
+                start = positions[i];
+            }
             chunk.push(c);
             if in_string {
                 // Fine della stringa - aggiungi il chunk
-                chunks.push(chunk.clone());
-                chunk.clear();
+                flush(&mut chunks, &mut chunk, start);
                 in_string = false;
             } else {
                 // Inizio della stringa
@@ -26,11 +110,13 @@ pub fn chunker(code: &str) -> Vec<String> {
         }
         // Gestione apici singoli (caratteri)
         else if c == '\'' && !in_string {
+            if chunk.is_empty() {
+                start = positions[i];
+            }
             chunk.push(c);
             if in_char {
                 // Fine del carattere - aggiungi il chunk
-                chunks.push(chunk.clone());
-                chunk.clear();
+                flush(&mut chunks, &mut chunk, start);
                 in_char = false;
             } else {
                 // Inizio del carattere
@@ -38,82 +124,68 @@ pub fn chunker(code: &str) -> Vec<String> {
             }
             i += 1;
         }
-        // Se siamo dentro una stringa o un carattere, aggiungi tutto
+        // Se siamo dentro una stringa o un carattere, aggiungi tutto. Dopo un backslash
+        // prendo anche il carattere seguente, così \" non chiude la stringa (gli escape
+        // li decodifica il tokenizer)
         else if in_string || in_char {
+            if chunk.is_empty() {
+                start = positions[i];
+            }
             chunk.push(c);
             i += 1;
+            if c == '\\' && i < chars.len() {
+                chunk.push(chars[i]);
+                i += 1;
+            }
         }
-        // Operatori a due caratteri: ==, !=, <=, >=
+        // Operatori a due caratteri: ==, !=, <=, >=, &&, ||, <<, >>
         else if i + 1 < chars.len() {
             let next_c = chars[i + 1];
-            if (c == '=' && next_c == '=') ||
-               (c == '!' && next_c == '=') ||
-               (c == '<' && next_c == '=') ||
-               (c == '>' && next_c == '=') {
+            if (next_c == '=' && "=!<>".contains(c)) || (next_c == c && "&|<>".contains(c)) {
                 // Salva chunk corrente se non vuoto
-                if !chunk.is_empty() {
-                    chunks.push(chunk.clone());
-                    chunk.clear();
-                }
+                flush(&mut chunks, &mut chunk, start);
                 // Aggiungi operatore a due caratteri
-                chunks.push(format!("{}{}", c, next_c));
+                let (line, column) = positions[i];
+                chunks.push(Chunk { text: format!("{}{}", c, next_c), span: Span::new(line, column, 2) });
                 i += 2;
             }
-            // Operatori singoli: +, -, *, /, =, <, >, !
-            else if "+-*/=<>!".contains(c) {
-                if !chunk.is_empty() {
-                    chunks.push(chunk.clone());
-                    chunk.clear();
-                }
-                chunks.push(c.to_string());
-                i += 1;
-            }
-            // Simboli speciali - separali sempre
-            else if "(){}[];,".contains(c) {
-                if !chunk.is_empty() {
-                    chunks.push(chunk.clone());
-                    chunk.clear();
-                }
-                chunks.push(c.to_string());
+            // Operatori singoli (+, -, *, /, %, &, |, ^, =, <, >, !, ~) e simboli speciali - separali sempre.
+            // Il punto (p.x) pure, tranne quando è dentro un numero tipo 3.14
+            else if "+-*/%&|^=<>!~(){}[];,".contains(c)
+                || (c == '.' && (chunk.is_empty() || !chunk.chars().all(|d| d.is_ascii_digit()))) {
+                flush(&mut chunks, &mut chunk, start);
+                let (line, column) = positions[i];
+                chunks.push(Chunk { text: c.to_string(), span: Span::new(line, column, 1) });
                 i += 1;
             }
             // Spazi bianchi
             else if c.is_whitespace() {
-                if !chunk.is_empty() {
-                    chunks.push(chunk.clone());
-                    chunk.clear();
-                }
+                flush(&mut chunks, &mut chunk, start);
                 i += 1;
             }
             // Caratteri normali
             else {
+                if chunk.is_empty() {
+                    start = positions[i];
+                }
                 chunk.push(c);
                 i += 1;
             }
         }
         // Ultimo carattere - stessa logica ma senza guardare next_c
         else {
-            if "+-*/=<>!".contains(c) {
-                if !chunk.is_empty() {
-                    chunks.push(chunk.clone());
-                    chunk.clear();
-                }
-                chunks.push(c.to_string());
-            }
-            else if "(){}[];,".contains(c) {
-                if !chunk.is_empty() {
-                    chunks.push(chunk.clone());
-                    chunk.clear();
-                }
-                chunks.push(c.to_string());
+            if "+-*/%&|^=<>!~(){}[];,".contains(c) {
+                flush(&mut chunks, &mut chunk, start);
+                let (line, column) = positions[i];
+                chunks.push(Chunk { text: c.to_string(), span: Span::new(line, column, 1) });
             }
             else if c.is_whitespace() {
-                if !chunk.is_empty() {
-                    chunks.push(chunk.clone());
-                    chunk.clear();
-                }
+                flush(&mut chunks, &mut chunk, start);
             }
             else {
+                if chunk.is_empty() {
+                    start = positions[i];
+                }
                 chunk.push(c);
             }
             i += 1;
@@ -121,15 +193,13 @@ pub fn chunker(code: &str) -> Vec<String> {
     }
 
     // Aggiungi l'ultimo chunk se non è vuoto
-    if !chunk.is_empty() {
-        chunks.push(chunk);
-    }
+    flush(&mut chunks, &mut chunk, start);
 
     println!("\nChunks generati:");
     println!("Totale: {}", chunks.len());
     for (i, chunk) in chunks.iter().enumerate() {
-        println!("  [{}] \"{}\"", i, chunk);
+        println!("  [{}] {} \"{}\"", i, chunk.span, chunk.text);
     }
 
-    return chunks;
+    chunks
 }
\ No newline at end of file
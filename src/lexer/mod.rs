@@ -1,6 +1,8 @@
 pub mod chunker;
+pub mod span;
 pub mod tokenizer;
 
 // Re-export per facilitare l'uso
 pub use chunker::chunker;
-pub use tokenizer::tokenizer;
\ No newline at end of file
+pub use span::Span;
+pub use tokenizer::tokenizer;
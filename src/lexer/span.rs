@@ -0,0 +1,30 @@
+use std::fmt;
+
+// Posizione di un pezzo di codice nel file sorgente (righe e colonne partono da 1)
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Span {
+    pub line: usize,
+    pub column: usize,
+    pub len: usize,
+}
+
+impl Span {
+    pub fn new(line: usize, column: usize, len: usize) -> Self {
+        Self { line, column, len }
+    }
+
+    // Unisce due span: se sono sulla stessa riga copre tutto, altrimenti resta sull'inizio
+    pub fn to(self, end: Span) -> Span {
+        if end.line == self.line && end.column + end.len > self.column {
+            Span::new(self.line, self.column, end.column + end.len - self.column)
+        } else {
+            self
+        }
+    }
+}
+
+impl fmt::Display for Span {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
@@ -1,3 +1,9 @@
+use crate::error::Diagnostic;
+use crate::lexer::chunker::Chunk;
+use crate::lexer::span::Span;
+use std::iter::Peekable;
+use std::str::Chars;
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum Token {
     // Tipi di dato
@@ -7,6 +13,7 @@ pub enum Token {
     Chad,       // char
     Vibes,      // string
     Ghost,      // void
+    NoCap,      // bool
 
     // Valori booleani
     Cap,        // false
@@ -14,10 +21,12 @@ pub enum Token {
 
     // Operatori e keywords
     Slay,       // = (assignment)
+    As,         // cast: x as based
     Plus,       // +
     Minus,      // -
     Star,       // *
     Slash,      // /
+    Percent,    // %
 
     // Operatori di comparazione
     Equal,      // == (uguaglianza)
@@ -26,6 +35,19 @@ pub enum Token {
     Greater,    // >
     LessEq,     // <=
     GreaterEq,  // >=
+
+    // Operatori logici
+    And,        // &&
+    Or,         // ||
+    Not,        // !
+
+    // Operatori bit a bit
+    Tilde,      // ~
+    Ampersand,  // &
+    Pipe,       // |
+    Caret,      // ^
+    ShiftLeft,  // <<
+    ShiftRight, // >>
     Flex,       // print
     Yeet,       // return
 
@@ -35,6 +57,7 @@ pub enum Token {
     Ong,        // if
     Mewing,     // while
     Ohio,       // break
+    Fax,        // continue
 
     // Funzioni e strutture
     Bussin,     // function
@@ -43,23 +66,41 @@ pub enum Token {
 
     // Puntatori
     Gyatt,      // pointer
+    Ghosted,    // null
+
+    // Costanti globali
+    LockedIn,   // const
 
     // Identificatori e letterali
     Rizz(String),      // identifier/variable name
     IntLit(i64),       // numero intero
-    StringLit(String), // stringa
+    FloatLit(f64),     // numero decimale (3.14)
+    StringLit(Vec<u8>), // stringa, in byte: "\xff" è un byte solo, non UTF-8
     CharLit(char),
 
     // Simboli
     OpenParen,    // (
     CloseParen,   // )
     OpenBrace,    // {
+    OpenBracket,  // [
+    CloseBracket, // ]
     CloseBrace,   // }
     Semicolon,    // ;
     Comma,        // ,
+    Dot,          // . (accesso ai campi)
 
     // Unknown
     Unknown(String),
+
+    // Fine del file (sempre l'ultimo token)
+    Eof,
+}
+
+// Token con la posizione da cui proviene nel sorgente
+#[derive(Debug, Clone)]
+pub struct SpannedToken {
+    pub token: Token,
+    pub span: Span,
 }
 
 fn is_valid_identifier(s: &str) -> bool {
@@ -78,145 +119,302 @@ fn is_valid_identifier(s: &str) -> bool {
     chars.all(|c| c.is_alphanumeric() || c == '_')
 }
 
-pub fn tokenizer(chunks: Vec<String>) -> Vec<Token> {
-    let mut tokens: Vec<Token> = Vec::new();
-    for chunk in chunks.iter() {
+// 3.14 -> Some(3.14); serve almeno una cifra prima del punto (.5 non vale)
+fn float_literal(s: &str) -> Option<f64> {
+    // cifre da entrambi i lati del punto: niente "1." o ".5"
+    let (int_part, frac_part) = s.split_once('.')?;
+    if !int_part.starts_with(|c: char| c.is_ascii_digit()) || !frac_part.starts_with(|c: char| c.is_ascii_digit()) {
+        return None;
+    }
+    s.parse().ok()
+}
+
+pub fn tokenizer(chunks: Vec<Chunk>) -> Result<Vec<SpannedToken>, Vec<Diagnostic>> {
+    let mut tokens: Vec<SpannedToken> = Vec::new();
+    for Chunk { text: chunk, span } in chunks.iter() {
         // Skip chunk vuoti
         if chunk.is_empty() {
             continue;
         }
 
         // 1. Prima controlla le keywords
-        if chunk.eq("based") {
-            tokens.push(Token::Based)
+        let token = if chunk.eq("based") {
+            Token::Based
         } else if chunk.eq("chill") {
-            tokens.push(Token::Chill)
+            Token::Chill
         } else if chunk.eq("superBased") {
-            tokens.push(Token::SuperBased)
+            Token::SuperBased
         } else if chunk.eq("vibes") {
-            tokens.push(Token::Vibes)
+            Token::Vibes
         } else if chunk.eq("ghost") {
-            tokens.push(Token::Ghost)
+            Token::Ghost
+        } else if chunk.eq("nocap") {
+            Token::NoCap
         } else if chunk.eq("cap") {
-            tokens.push(Token::Cap)
+            Token::Cap
         } else if chunk.eq("fr") {
-            tokens.push(Token::Fr)
+            Token::Fr
         } else if chunk.eq("slay") {
-            tokens.push(Token::Slay)
+            Token::Slay
+        } else if chunk.eq("as") {
+            Token::As
         } else if chunk.eq("==") {
-            tokens.push(Token::Equal)
+            Token::Equal
         } else if chunk.eq("!=") {
-            tokens.push(Token::NotEqual)
+            Token::NotEqual
+        } else if chunk.eq("&&") {
+            Token::And
+        } else if chunk.eq("||") {
+            Token::Or
+        } else if chunk.eq("!") {
+            Token::Not
+        } else if chunk.eq("%") {
+            Token::Percent
+        } else if chunk.eq("&") {
+            Token::Ampersand
+        } else if chunk.eq("|") {
+            Token::Pipe
+        } else if chunk.eq("^") {
+            Token::Caret
+        } else if chunk.eq("<<") {
+            Token::ShiftLeft
+        } else if chunk.eq(">>") {
+            Token::ShiftRight
+        } else if chunk.eq("~") {
+            Token::Tilde
         } else if chunk.eq("-") {
-            tokens.push(Token::Minus)
+            Token::Minus
         } else if chunk.eq("+") {
-            tokens.push(Token::Plus)
+            Token::Plus
         } else if chunk.eq("*") {
-            tokens.push(Token::Star)
+            Token::Star
         } else if chunk.eq("/") {
-            tokens.push(Token::Slash)
+            Token::Slash
         } else if chunk.eq("<") {
-            tokens.push(Token::Less)
+            Token::Less
         } else if chunk.eq(">") {
-            tokens.push(Token::Greater)
+            Token::Greater
         } else if chunk.eq("<=") {
-            tokens.push(Token::LessEq)
+            Token::LessEq
         } else if chunk.eq(">=") {
-            tokens.push(Token::GreaterEq)
+            Token::GreaterEq
         } else if chunk.eq("flex") {
-            tokens.push(Token::Flex)
+            Token::Flex
         } else if chunk.eq("chad") {
-            tokens.push(Token::Chad)
+            Token::Chad
         } else if chunk.eq("yeet") {
-            tokens.push(Token::Yeet)
+            Token::Yeet
         } else if chunk.eq("sixSeven") {
-            tokens.push(Token::SixSeven)
+            Token::SixSeven
         } else if chunk.eq("ong") {
-            tokens.push(Token::Ong)
+            Token::Ong
         } else if chunk.eq("nah") {
-            tokens.push(Token::Nah)
+            Token::Nah
         } else if chunk.eq("mewing") {
-            tokens.push(Token::Mewing)
+            Token::Mewing
         } else if chunk.eq("ohio") {
-            tokens.push(Token::Ohio)
+            Token::Ohio
+        } else if chunk.eq("fax") {
+            Token::Fax
         } else if chunk.eq("bussin") {
-            tokens.push(Token::Bussin)
+            Token::Bussin
         } else if chunk.eq("sigma") {
-            tokens.push(Token::Sigma)
+            Token::Sigma
         } else if chunk.eq("skibidi") {
-            tokens.push(Token::Skibidi)
+            Token::Skibidi
         } else if chunk.eq("gyatt") {
-            tokens.push(Token::Gyatt)
+            Token::Gyatt
+        } else if chunk.eq("ghosted") {
+            Token::Ghosted
+        } else if chunk.eq("lockedIn") {
+            Token::LockedIn
 
         // 2. Simboli
         } else if chunk.eq("(") {
-            tokens.push(Token::OpenParen)
+            Token::OpenParen
         } else if chunk.eq(")") {
-            tokens.push(Token::CloseParen)
+            Token::CloseParen
+        } else if chunk.eq(".") {
+            Token::Dot
+        } else if chunk.eq("[") {
+            Token::OpenBracket
+        } else if chunk.eq("]") {
+            Token::CloseBracket
         } else if chunk.eq("{") {
-            tokens.push(Token::OpenBrace)
+            Token::OpenBrace
         } else if chunk.eq("}") {
-            tokens.push(Token::CloseBrace)
+            Token::CloseBrace
         } else if chunk.eq(";") {
-            tokens.push(Token::Semicolon)
+            Token::Semicolon
         } else if chunk.eq(",") {
-            tokens.push(Token::Comma)
+            Token::Comma
 
-        // 3. Numeri (solo interi, float non supportati)
+        // 3. Numeri: interi, oppure decimali se c'è il punto
         } else if let Ok(num) = chunk.parse::<i64>() {
             // è un intero bello grande
-            tokens.push(Token::IntLit(num))
+            Token::IntLit(num)
+        } else if let Some(num) = float_literal(chunk) {
+            Token::FloatLit(num)
 
         // 4. Stringhe letterali (tra virgolette) (Vibes)
         } else if chunk.starts_with('"') && chunk.ends_with('"') && chunk.len() > 1 {
-            let content = chunk[1..chunk.len()-1].to_string();
-            tokens.push(Token::StringLit(content))
+            match unescape(&chunk[1..chunk.len()-1]) {
+                Ok(content) => Token::StringLit(content),
+                Err(_) => Token::Unknown(chunk.clone())
+            }
 
         // 5. Caratteri letterali (tra virgolette semplici) (Chad)
         } else if chunk.starts_with('\'') && chunk.ends_with('\'') && chunk.len() >= 3 {
             // Estrai il contenuto tra gli apici
             let content = &chunk[1..chunk.len()-1];
 
-            let ch = if content.starts_with('\\') && content.len() == 2 {
-                // Escape sequence
-                match content.chars().nth(1).unwrap() {
-                    'n' => '\n',    // newline
-                    't' => '\t',    // tab
-                    'r' => '\r',    // carriage return
-                    '0' => '\0',    // null
-                    '\\' => '\\',   // backslash
-                    '\'' => '\'',   // apice singolo
-                    '"' => '"',     // virgoletta doppia
-                    _ => {
-                        tokens.push(Token::Unknown(chunk.clone()));
-                        continue;
-                    }
-                }
-            } else if content.len() == 1 {
-                // Carattere normale
-                content.chars().next().unwrap()
-            } else {
-                // Errore: troppi caratteri
-                tokens.push(Token::Unknown(chunk.clone()));
-                continue;
+            // Stessi escape delle stringhe, ma alla fine deve restare un byte solo
+            // ('\xe8') o un carattere solo che stia in un byte ('è')
+            let ch = match unescape(content) {
+                Ok(bytes) if bytes.len() == 1 => Some(bytes[0] as char),
+                Ok(bytes) => match std::str::from_utf8(&bytes).map(|text| text.chars().collect::<Vec<_>>()) {
+                    Ok(chars) if chars.len() == 1 && (chars[0] as u32) <= 0xFF => Some(chars[0]),
+                    _ => None
+                },
+                Err(_) => None
             };
 
-            tokens.push(Token::CharLit(ch))
+            match ch {
+                Some(ch) => Token::CharLit(ch),
+                None => Token::Unknown(chunk.clone())
+            }
 
         // 5. Identificatori (Rizz)
         } else if is_valid_identifier(chunk) {
-            tokens.push(Token::Rizz(chunk.clone()))
+            Token::Rizz(chunk.clone())
 
         // 6. Unknown
         } else {
-            tokens.push(Token::Unknown(chunk.clone()))
-        }
+            Token::Unknown(chunk.clone())
+        };
+
+        tokens.push(SpannedToken { token, span: *span });
     }
 
+    // Token di fine file subito dopo l'ultimo chunk
+    let eof_span = match chunks.last() {
+        Some(last) => Span::new(last.span.line, last.span.column + last.span.len, 1),
+        None => Span::new(1, 1, 1)
+    };
+    tokens.push(SpannedToken { token: Token::Eof, span: eof_span });
+
     println!("\nTokens generati:");
     for token in tokens.iter() {
-        println!("{:?}", token);
+        println!("{} {:?}", token.span, token.token);
+    }
+
+    // Ogni chunk non riconosciuto è un errore lessicale
+    let errors: Vec<Diagnostic> = tokens.iter().filter_map(|t| match &t.token {
+        Token::Unknown(text) => Some(unknown_token_error(text, t.span)),
+        _ => None
+    }).collect();
+    if !errors.is_empty() {
+        return Err(errors);
     }
 
-    return tokens;
+    Ok(tokens)
+}
+
+// Decodifica gli escape di una stringa o di un chad (senza le virgolette intorno).
+// Il risultato è in byte: i caratteri normali e \u{...} in UTF-8, \xNN così com'è.
+// In caso di errore ritorna l'escape sbagliato, così il messaggio può citarlo
+fn unescape(content: &str) -> Result<Vec<u8>, String> {
+    let mut result = Vec::new();
+    let mut chars = content.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        let mut buffer = [0; 4];
+        if c == '\\' {
+            match decode_escape(&mut chars)? {
+                Escape::Byte(byte) => result.push(byte),
+                Escape::Char(c) => result.extend_from_slice(c.encode_utf8(&mut buffer).as_bytes())
+            }
+        } else {
+            result.extend_from_slice(c.encode_utf8(&mut buffer).as_bytes());
+        }
+    }
+
+    Ok(result)
+}
+
+// Cosa vale un escape: un byte crudo (\xNN) o un carattere da scrivere in UTF-8
+enum Escape {
+    Byte(u8),
+    Char(char)
+}
+
+// Il valore di un escape, letto subito dopo il backslash
+fn decode_escape(chars: &mut Peekable<Chars>) -> Result<Escape, String> {
+    match chars.next() {
+        Some('n') => Ok(Escape::Byte(b'\n')),    // newline
+        Some('t') => Ok(Escape::Byte(b'\t')),    // tab
+        Some('r') => Ok(Escape::Byte(b'\r')),    // carriage return
+        Some('0') => Ok(Escape::Byte(0)),        // null
+        Some('\\') => Ok(Escape::Byte(b'\\')),   // backslash
+        Some('\'') => Ok(Escape::Byte(b'\'')),   // apice singolo
+        Some('"') => Ok(Escape::Byte(b'"')),     // virgoletta doppia
+        // \xNN: un byte qualsiasi, anche sopra 7F
+        Some('x') => {
+            let digits: String = (0..2).map_while(|_| chars.next_if(char::is_ascii_hexdigit)).collect();
+            match u8::from_str_radix(&digits, 16) {
+                Ok(byte) if digits.len() == 2 => Ok(Escape::Byte(byte)),
+                _ => Err(format!("\\x{}", digits))
+            }
+        },
+        // \u{...}: un carattere unicode, da 1 a 6 cifre esadecimali
+        Some('u') => {
+            if chars.next_if_eq(&'{').is_none() {
+                return Err("\\u".to_string());
+            }
+            let mut digits = String::new();
+            while let Some(d) = chars.next_if(char::is_ascii_hexdigit) {
+                digits.push(d);
+            }
+            if chars.next_if_eq(&'}').is_none() || digits.is_empty() || digits.len() > 6 {
+                return Err(format!("\\u{{{}", digits));
+            }
+            u32::from_str_radix(&digits, 16).ok().and_then(char::from_u32).map(Escape::Char)
+                .ok_or_else(|| format!("\\u{{{}}}", digits))
+        },
+        Some(other) => Err(format!("\\{}", other)),
+        // backslash in fondo: in realtà era la virgoletta di chiusura a essere escapata
+        None => Err("\\".to_string())
+    }
+}
+
+// Spiega perché un chunk non è un token valido
+fn unknown_token_error(text: &str, span: Span) -> Diagnostic {
+    // una stringa chiusa che non è diventata StringLit ha un escape sbagliato
+    let bad_escape = match text.strip_prefix('"').and_then(|t| t.strip_suffix('"')) {
+        Some(content) => unescape(content).err().filter(|escape| escape != "\\"),
+        None => None
+    };
+
+    if let Some(escape) = bad_escape {
+        Diagnostic::error(span, format!("Invalid escape sequence '{}' in string literal", escape))
+            .with_help("valid escapes are \\n, \\t, \\r, \\0, \\\\, \\', \\\", \\xNN and \\u{...}")
+    } else if text.starts_with('"') {
+        Diagnostic::error(span, "Unterminated string literal")
+            .with_help("close the string with '\"'")
+    } else if text.starts_with('\'') {
+        Diagnostic::error(span, format!("Invalid character literal {}", text))
+            .with_help("a chad literal holds exactly one character or escape, e.g. 'a' or '\\n'")
+    } else if text == "/*" {
+        Diagnostic::error(span, "Unterminated block comment")
+            .with_help("close the comment with '*/'")
+    } else if text.starts_with(|c: char| c.is_ascii_digit()) && text.contains('.') && text.chars().all(|c| c.is_ascii_digit() || c == '.') {
+        Diagnostic::error(span, format!("Invalid number literal '{}'", text))
+            .with_note("chill literals are written with digits on both sides of the dot, e.g. 3.14")
+    } else if text.chars().all(|c| c.is_ascii_digit()) {
+        // solo cifre decimali: l'unico modo di sbagliare è un numero troppo grande
+        Diagnostic::error(span, format!("Invalid number literal '{}'", text))
+            .with_note("integer literals must fit in 64 bits")
+    } else {
+        Diagnostic::error(span, format!("Unknown token '{}'", text))
+    }
 }
\ No newline at end of file
@@ -3,6 +3,8 @@ mod compiler;
 mod parser;
 mod semantic;
 mod codegen;
+mod error;
+mod builtins;
 
 use std::env;
 use std::fs;
@@ -74,9 +76,16 @@ fn main() {
 
     // Compila il codice Sigma -> RISC-V assembly
     match compile(&code, &output_path) {
-        Ok(_) => println!("\n✓ Compilazione completata con successo!"),
-        Err(err) => {
-            eprintln!("✗ Errore di compilazione: {}", err);
+        Ok(()) => {
+            println!("\n✓ Compilazione completata con successo!");
+        },
+        Err(diagnostics) => {
+            for diagnostic in &diagnostics {
+                eprintln!("{}", diagnostic.render(&file_path, &code));
+            }
+
+            let errors = diagnostics.len();
+            eprintln!("✗ Errore di compilazione: {} {}", errors, if errors == 1 { "errore" } else { "errori" });
             process::exit(1);
         }
     }
@@ -136,8 +145,8 @@ fn assemble_riscv(asm_file: &str, output_file: &str) -> Result<(), String> {
     for gcc in &gcc_variants {
         if is_command_available(gcc) {
             let status = Command::new(gcc)
-                .args(&[
-                    "-march=rv32im",
+                .args([
+                    "-march=rv32imf",
                     "-mabi=ilp32",
                     asm_file,
                     "-o", output_file,
@@ -226,32 +235,32 @@ fn execute_qemu(executable: &str) -> Result<(), String> {
     }
 
     #[cfg(target_os = "macos")]
-    return Err(format!(
+    return Err(
         "Impossibile eseguire il programma.\n\
         Su macOS, QEMU user-mode non è disponibile nativamente.\n\
         Opzioni:\n\
         1. Usa solo compilazione (senza -x)\n\
         2. Installa spike + pk: brew install riscv-isa-sim riscv-pk\n\
-        3. Usa Docker/VM Linux con qemu-user"
-    ));
+        3. Usa Docker/VM Linux con qemu-user".to_string()
+    );
 
     #[cfg(target_os = "windows")]
-    return Err(format!(
+    return Err(
         "QEMU user-mode non trovato.\n\
         Su Windows, installa WSL e qemu-user:\n\
         1. wsl --install -d Ubuntu\n\
         2. wsl\n\
-        3. sudo apt update && sudo apt install qemu-user"
-    ));
+        3. sudo apt update && sudo apt install qemu-user".to_string()
+    );
 
     #[cfg(target_os = "linux")]
-    return Err(format!(
+    return Err(
         "QEMU user-mode non trovato.\n\
         Installa qemu-user:\n\
         - Ubuntu/Debian: sudo apt install qemu-user\n\
         - Fedora: sudo dnf install qemu-user\n\
-        - Arch: sudo pacman -S qemu-user"
-    ));
+        - Arch: sudo pacman -S qemu-user".to_string()
+    );
 
     #[allow(unreachable_code)]
     Err("Impossibile eseguire il programma.".to_string())
@@ -1,23 +1,78 @@
+use crate::lexer::Span;
+
 #[derive(Debug)]
 pub struct Program {
+    pub structs: Vec<StructDef>,
+    pub globals: Vec<Global>,
     pub functions: Vec<Function>
 }
 
+// skibidi Point { based x; based y; }
+#[derive(Debug)]
+pub struct StructDef {
+    pub name: String,
+    pub fields: Vec<StructField>,
+    pub span: Span  // posizione del nome dello struct
+}
+
+#[derive(Debug)]
+pub struct StructField {
+    pub name: String,
+    pub field_type: Type,
+    pub span: Span
+}
+
+// Variabile globale (based counter slay 0;) o costante (lockedIn based MAX slay 10;):
+// dichiarata fuori dalle funzioni e visibile in tutte
+#[derive(Debug)]
+pub struct Global {
+    pub name: String,
+    pub var_type: Type,
+    pub value: Expression,  // valore iniziale, deve essere un letterale
+    pub constant: bool,     // lockedIn: non si può riassegnare
+    pub span: Span,
+    pub decl: Option<DeclId>
+}
+
 #[derive(Debug)]
 pub struct Function {
     pub name: String,
     pub return_type: Type,
     pub parameters: Vec<Parameter>,
-    pub body: Vec<Statement>
+    pub body: Vec<Statement>,
+    pub span: Span  // posizione del nome della funzione
+}
+
+// Firma della funzione (o builtin) chiamata da una Call, la risolve il semantic:
+// al codegen servono i tipi dei parametri per scegliere i registri e il tipo di ritorno
+#[derive(Debug, Clone)]
+pub struct Signature {
+    pub parameters: Vec<Type>,
+    pub return_type: Type
+}
+
+// Id unico di una dichiarazione (variabile, globale, array, struct o parametro), assegnato dal semantic.
+// Due variabili con lo stesso nome in scope diversi hanno id diversi
+pub type DeclId = usize;
+
+#[derive(Debug)]
+pub struct Statement {
+    pub kind: StatementKind,
+    pub span: Span
 }
 
 #[derive(Debug)]
-pub enum Statement {
-    VarDecl { var_type: Type, name: String, value: Expression },
-    Assignment { name: String, value: Expression },
+pub enum StatementKind {
+    VarDecl { var_type: Type, name: String, value: Expression, decl: Option<DeclId> },
+    ArrayDecl { elem_type: Type, name: String, size: usize, decl: Option<DeclId> },  // based arr[10];
+    StructVarDecl { struct_name: String, name: String, decl: Option<DeclId> },        // Point p;
+    Assignment { name: String, value: Expression, decl: Option<DeclId>, var_type: Option<Type> },  // var_type lo trova il semantic
+    Store { target: Expression, value: Expression },  // scrittura in memoria: arr[i] slay v; p.x slay v;
     Print { expr: Expression },
     Return { expr: Option<Expression> },
+    Call { name: String, args: Vec<Expression>, signature: Option<Signature> },  // chiamata usata come statement: foo(x);
     Break,  // ohio - esce dal loop
+    Continue, // fax - passa alla prossima iterazione
 
     // Costrutti di controllo implementati da me porca puttana
     If {
@@ -42,20 +97,56 @@ pub enum Statement {
 #[derive(Debug)]
 pub struct Parameter {
     pub name: String,
-    pub parameter_type: Type
+    pub parameter_type: Type,
+    pub span: Span,
+    pub decl: Option<DeclId>
 }
 
 #[derive(Debug)]
-pub enum Expression {
+pub struct Expression {
+    pub kind: ExpressionKind,
+    pub span: Span,
+    pub resolved_type: Option<Type>  // lo riempie il semantic, None finché non è stato analizzato
+}
+
+#[derive(Debug)]
+pub enum ExpressionKind {
     Integer(i32),       // based - numeri interi piccoli
     Long(i64),          // superBased - numeri interi grandi
-    StringLit(String),  // vibes - stringhe
+    Float(f64),         // chill - numeri decimali
+    StringLit(Vec<u8>),  // vibes - stringhe, in byte
     CharLit(char),      // Chad - carattere
-    Variable(String),    // nome di variabile
+    Bool(bool),         // nocap - fr / cap
+    Null,               // ghosted - puntatore che non punta a niente
+    Variable {
+        name: String,              // nome di variabile
+        decl: Option<DeclId>       // dichiarazione a cui si riferisce, la trova il semantic
+    },
+    Index {
+        array: Box<Expression>,  // array da cui leggere
+        index: Box<Expression>   // posizione dell'elemento: arr[i]
+    },
+    Field {
+        base: Box<Expression>,   // struct da cui leggere
+        field: String            // nome del campo: p.x
+    },
+    Call {
+        name: String,            // nome della funzione bussin chiamata
+        args: Vec<Expression>,   // argomenti passati in a0..a7
+        signature: Option<Signature>  // firma della funzione chiamata, la trova il semantic
+    },
     BinOp {
         left: Box<Expression>,
         op: BinOp,
         right: Box<Expression>
+    },
+    UnaryOp {
+        op: UnaryOp,
+        operand: Box<Expression>
+    },
+    Cast {
+        expr: Box<Expression>,   // valore da convertire
+        target: Type             // tipo di arrivo: c as based
     }
 }
 
@@ -66,6 +157,14 @@ pub enum BinOp {
     Sub,      // -
     Mul,      // *
     Div,      // /
+    Mod,      // %
+
+    // Operatori bit a bit
+    BitAnd,   // &
+    BitOr,    // |
+    BitXor,   // ^
+    Shl,      // <<
+    Shr,      // >> (aritmetico, mantiene il segno)
 
     // Operatori di comparazione
     Equal,    // ==
@@ -73,7 +172,20 @@ pub enum BinOp {
     Less,     // <
     Greater,  // >
     LessEq,   // <=
-    GreaterEq // >=
+    GreaterEq, // >=
+
+    // Operatori logici (short-circuit)
+    And,      // &&
+    Or        // ||
+}
+
+#[derive(Debug)]
+pub enum UnaryOp {
+    Neg,      // -x
+    Not,      // !
+    BitNot,   // ~
+    AddressOf, // &x - indirizzo di una variabile, elemento o campo
+    Deref     // *p - valore puntato da p
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -83,5 +195,17 @@ pub enum Type {
     Chill,      // float
     Vibes,      // string
     Chad,       // char
-    Ghost       // void
+    NoCap,      // bool (fr / cap)
+    Ghost,      // void
+
+    // Array di elementi dello stesso tipo. La dimensione è None per i parametri
+    // (based arr[]), che ricevono solo l'indirizzo dell'array del chiamante
+    Array(Box<Type>, Option<usize>),
+
+    // Struct dichiarato con skibidi, identificato dal nome
+    Struct(String),
+
+    // gyatt based p: indirizzo di un valore del tipo interno.
+    // Pointer(Ghost) è il tipo di 'ghosted', compatibile con qualsiasi puntatore
+    Pointer(Box<Type>)
 }
\ No newline at end of file
@@ -1,46 +1,147 @@
-use crate::lexer::tokenizer::Token;
+use crate::error::Diagnostic;
+use crate::lexer::tokenizer::{SpannedToken, Token};
+use crate::lexer::Span;
 use crate::parser::ast::*;
 
-fn parse_function(tokens: &[Token], index: &mut usize) -> Result<Function, String> {
+// Errore posizionato sul token corrente
+fn error_at(tokens: &[SpannedToken], index: usize, msg: &str) -> Diagnostic {
+    Diagnostic::error(tokens[index].span, msg)
+}
+
+// Errore per un ';' mancante: lo segnalo subito dopo l'ultimo token consumato, come fa rustc
+fn missing_semicolon(tokens: &[SpannedToken], index: usize, msg: &str) -> Diagnostic {
+    let previous = tokens[index - 1].span;
+    let span = Span::new(previous.line, previous.column + previous.len, 1);
+    Diagnostic::error(span, msg)
+        .with_label(tokens[index].span, format!("unexpected {:?}", tokens[index].token))
+        .with_help("add ';' here")
+}
+
+// Panic mode: dopo un errore salto token finché non trovo un punto sicuro da cui ripartire.
+// Mi fermo dopo un ';', prima di un '}' che chiude il blocco corrente, o su 'bussin'/'skibidi'/fine file.
+// I blocchi { ... } incontrati per strada vengono saltati interi.
+fn synchronize(tokens: &[SpannedToken], index: &mut usize) {
+    let mut depth = 0;
+
+    loop {
+        match tokens[*index].token {
+            Token::Eof | Token::Bussin | Token::Skibidi => return,
+            Token::Semicolon if depth == 0 => {
+                *index += 1;
+                return;
+            },
+            Token::OpenBrace => depth += 1,
+            Token::CloseBrace => {
+                if depth == 0 {
+                    return;
+                }
+                depth -= 1;
+                if depth == 0 {
+                    *index += 1;
+                    return;
+                }
+            },
+            _ => {}
+        }
+        *index += 1;
+    }
+}
+
+// Token con cui può iniziare una globale: 'lockedIn' o un tipo
+fn starts_global(token: &Token) -> bool {
+    matches!(token, Token::LockedIn | Token::Based | Token::SuperBased | Token::Chill | Token::Vibes | Token::Chad | Token::NoCap | Token::Gyatt)
+}
+
+// Dopo un errore in una funzione o in uno struct salto alla prossima dichiarazione top-level:
+// 'bussin', 'skibidi', fine file, oppure una globale, ma solo fuori dalle graffe e subito dopo
+// un ';' o un '}' (un 'based' tra i parametri o nel body non è una globale). Le graffe aperte
+// prima dell'errore le conto dall'inizio della dichiarazione
+fn skip_to_declaration(tokens: &[SpannedToken], start: usize, index: &mut usize) {
+    let nesting = |token: &Token| match token {
+        Token::OpenBrace => 1,
+        Token::CloseBrace => -1,
+        _ => 0
+    };
+    let mut depth: i32 = tokens[start..*index].iter().map(|t| nesting(&t.token)).sum();
+
+    loop {
+        let token = &tokens[*index].token;
+        let after_end = *index > 0 && matches!(tokens[*index - 1].token, Token::Semicolon | Token::CloseBrace);
+        if matches!(token, Token::Bussin | Token::Skibidi | Token::Eof) || (depth <= 0 && after_end && starts_global(token)) {
+            return;
+        }
+        depth += nesting(token);
+        *index += 1;
+    }
+}
+
+// Consuma i 'gyatt' davanti a un tipo e ritorna quanti erano (gyatt gyatt based = puntatore a puntatore)
+fn parse_gyatt(tokens: &[SpannedToken], index: &mut usize) -> usize {
+    let mut depth = 0;
+    while matches!(tokens[*index].token, Token::Gyatt) {
+        depth += 1;
+        *index += 1;
+    }
+    depth
+}
+
+// Avvolge il tipo base in tanti Pointer quanti erano i 'gyatt'
+fn pointer_to(base: Type, depth: usize) -> Type {
+    (0..depth).fold(base, |inner, _| Type::Pointer(Box::new(inner)))
+}
+
+// Span che va dal token di inizio fino all'ultimo token consumato
+fn span_from(tokens: &[SpannedToken], start: usize, index: usize) -> Span {
+    tokens[start].span.to(tokens[index - 1].span)
+}
+
+fn parse_function(tokens: &[SpannedToken], index: &mut usize, errors: &mut Vec<Diagnostic>) -> Result<Function, Diagnostic> {
     *index += 1;
 
-    let return_type = match &tokens[*index] {
+    let depth = parse_gyatt(tokens, index);
+    let return_type = match &tokens[*index].token {
         Token::Based => Type::Based,
         Token::SuperBased => Type::SuperBased,
         Token::Chill => Type::Chill,
         Token::Vibes => Type::Vibes,
+        Token::Chad => Type::Chad,
+        Token::NoCap => Type::NoCap,
         Token::Ghost => Type::Ghost,
-        _ => return Err("Expected return type after 'bussin'".to_string())
+        // uno struct si può ritornare solo tramite puntatore
+        Token::Rizz(n) if depth > 0 => Type::Struct(n.clone()),
+        _ => return Err(error_at(tokens, *index, "Expected return type after 'bussin'"))
     };
+    let return_type = pointer_to(return_type, depth);
 
     *index += 1;
 
-    let name = match &tokens[*index] {
+    let span = tokens[*index].span;
+    let name = match &tokens[*index].token {
         Token::Sigma => "sigma".to_string(),
         Token::Rizz(n) => n.clone(),
-        _ => return Err("Expected function name after type of bussin".to_string())
+        _ => return Err(error_at(tokens, *index, "Expected function name after type of bussin"))
     };
 
     *index += 1;
 
-    if !matches!(tokens[*index], Token::OpenParen) {
-        return Err("Expected '(' after function name".to_string());
+    if !matches!(tokens[*index].token, Token::OpenParen) {
+        return Err(error_at(tokens, *index, "Expected '(' after function name"));
     }
 
     *index += 1;
 
     let parameters = parse_parameters(tokens, index)?;
 
-    if !matches!(tokens[*index], Token::OpenBrace) {
-        return Err("Expected '{' to start function body".to_string());
+    if !matches!(tokens[*index].token, Token::OpenBrace) {
+        return Err(error_at(tokens, *index, "Expected '{' to start function body"));
     }
 
     *index += 1;
 
-    let body = parse_body(tokens, index)?;
+    let body = parse_body(tokens, index, errors)?;
 
-    if !matches!(tokens[*index], Token::CloseBrace) {
-        return Err("Expected '}' to end function body".to_string());
+    if !matches!(tokens[*index].token, Token::CloseBrace) {
+        return Err(error_at(tokens, *index, "Expected '}' to end function body"));
     }
 
     *index += 1;
@@ -49,15 +150,16 @@ fn parse_function(tokens: &[Token], index: &mut usize) -> Result<Function, Strin
         name,
         return_type,
         parameters,
-        body
+        body,
+        span
     })
 }
 
-fn parse_parameters(tokens: &[Token], index: &mut usize) -> Result<Vec<Parameter>, String> {
+fn parse_parameters(tokens: &[SpannedToken], index: &mut usize) -> Result<Vec<Parameter>, Diagnostic> {
     let mut parameters = Vec::new();
 
     // Se c'è subito ), non ci sono parametri
-    if matches!(tokens[*index], Token::CloseParen) {
+    if matches!(tokens[*index].token, Token::CloseParen) {
         *index += 1; // Consuma )
         return Ok(parameters);
     }
@@ -65,31 +167,49 @@ fn parse_parameters(tokens: &[Token], index: &mut usize) -> Result<Vec<Parameter
     // Altrimenti, parsare i parametri in loop
     loop {
         // Parse tipo del parametro
-        let parameter_type = match &tokens[*index] {
+        let depth = parse_gyatt(tokens, index);
+        let parameter_type = match &tokens[*index].token {
             Token::Based => Type::Based,
             Token::SuperBased => Type::SuperBased,
             Token::Chill => Type::Chill,
             Token::Vibes => Type::Vibes,
             Token::Chad => Type::Chad,
+            Token::NoCap => Type::NoCap,
             Token::Ghost => Type::Ghost,
-            _ => return Err("Expected parameter type".to_string())
+            // gli struct si passano per puntatore: gyatt Point p
+            Token::Rizz(n) if depth > 0 => Type::Struct(n.clone()),
+            _ => return Err(error_at(tokens, *index, "Expected parameter type"))
         };
+        let parameter_type = pointer_to(parameter_type, depth);
 
         *index += 1;
 
         // Parse nome del parametro
-        let name = match &tokens[*index] {
+        let span = tokens[*index].span;
+        let name = match &tokens[*index].token {
             Token::Rizz(n) => n.clone(),
-            _ => return Err("Expected parameter name after type".to_string())
+            _ => return Err(error_at(tokens, *index, "Expected parameter name after type"))
         };
 
         *index += 1;
 
+        // 'based arr[]': l'array viene passato per riferimento, la dimensione la sa il chiamante
+        let parameter_type = if matches!(tokens[*index].token, Token::OpenBracket) {
+            *index += 1;
+            if !matches!(tokens[*index].token, Token::CloseBracket) {
+                return Err(error_at(tokens, *index, "Expected ']' in array parameter"));
+            }
+            *index += 1;
+            Type::Array(Box::new(parameter_type), None)
+        } else {
+            parameter_type
+        };
+
         // Aggiungi il parametro al vettore
-        parameters.push(Parameter { name, parameter_type });
+        parameters.push(Parameter { name, parameter_type, span, decl: None });
 
         // Controlla cosa viene dopo: virgola o )
-        match &tokens[*index] {
+        match &tokens[*index].token {
             Token::Comma => {
                 *index += 1; // Consuma la virgola e continua al prossimo parametro
             },
@@ -97,118 +217,356 @@ fn parse_parameters(tokens: &[Token], index: &mut usize) -> Result<Vec<Parameter
                 *index += 1; // Consuma ) e termina
                 break;
             },
-            _ => return Err("Expected ',' or ')' after parameter".to_string())
+            _ => return Err(error_at(tokens, *index, "Expected ',' or ')' after parameter"))
         }
     }
 
     Ok(parameters)
 }
 
+// Parse struct: skibidi Point { based x; chad tag; Other inner; based v[4]; }
+fn parse_struct_def(tokens: &[SpannedToken], index: &mut usize) -> Result<StructDef, Diagnostic> {
+    *index += 1;  // consuma 'skibidi'
+
+    let span = tokens[*index].span;
+    let name = match &tokens[*index].token {
+        Token::Rizz(n) => n.clone(),
+        _ => return Err(error_at(tokens, *index, "Expected struct name after 'skibidi'"))
+    };
+    *index += 1;
+
+    if !matches!(tokens[*index].token, Token::OpenBrace) {
+        return Err(error_at(tokens, *index, "Expected '{' after struct name"));
+    }
+    *index += 1;
+
+    let mut fields = Vec::new();
+    while !matches!(tokens[*index].token, Token::CloseBrace) {
+        let depth = parse_gyatt(tokens, index);
+        let field_type = match &tokens[*index].token {
+            Token::Based => Type::Based,
+            Token::SuperBased => Type::SuperBased,
+            Token::Chill => Type::Chill,
+            Token::Vibes => Type::Vibes,
+            Token::Chad => Type::Chad,
+            Token::NoCap => Type::NoCap,
+            Token::Rizz(n) => Type::Struct(n.clone()),
+            _ => return Err(error_at(tokens, *index, "Expected field type or '}' in struct"))
+        };
+        // 'gyatt Node next;' è ciò che permette liste e alberi
+        let field_type = pointer_to(field_type, depth);
+        *index += 1;
+
+        let field_span = tokens[*index].span;
+        let field_name = match &tokens[*index].token {
+            Token::Rizz(n) => n.clone(),
+            _ => return Err(error_at(tokens, *index, "Expected field name after type"))
+        };
+        *index += 1;
+
+        // campo array: based v[4];
+        let field_type = if matches!(tokens[*index].token, Token::OpenBracket) {
+            *index += 1;
+            let size = match &tokens[*index].token {
+                Token::IntLit(n) if *n > 0 => *n as usize,
+                _ => return Err(error_at(tokens, *index, "Expected a positive array size"))
+            };
+            *index += 1;
+            if !matches!(tokens[*index].token, Token::CloseBracket) {
+                return Err(error_at(tokens, *index, "Expected ']' after array size"));
+            }
+            *index += 1;
+            Type::Array(Box::new(field_type), Some(size))
+        } else {
+            field_type
+        };
+
+        if !matches!(tokens[*index].token, Token::Semicolon) {
+            return Err(missing_semicolon(tokens, *index, "Expected ';' after struct field"));
+        }
+        *index += 1;
+
+        fields.push(StructField { name: field_name, field_type, span: field_span });
+    }
+    *index += 1;  // consuma '}'
+
+    Ok(StructDef { name, fields, span })
+}
+
+// based counter slay 0; oppure lockedIn based MAX slay 10; fuori dalle funzioni
+fn parse_global(tokens: &[SpannedToken], index: &mut usize) -> Result<Global, Diagnostic> {
+    let start = *index;
+    let constant = matches!(tokens[*index].token, Token::LockedIn);
+    if constant {
+        *index += 1;
+    }
+
+    let stmt = parse_var_decl(tokens, index)?;
+    match stmt.kind {
+        StatementKind::VarDecl { var_type, name, value, .. } => Ok(Global {
+            name,
+            var_type,
+            value,
+            constant,
+            span: span_from(tokens, start, *index),
+            decl: None
+        }),
+        _ => Err(Diagnostic::error(stmt.span, "Arrays and structs cannot be declared at top level")
+            .with_help("declare them inside a function, globals hold a single value"))
+    }
+}
+
 // ==================== FUNZIONI HELPER PER GLI STATEMENT ====================
 
-fn parse_var_decl(tokens: &[Token], index: &mut usize) -> Result<Statement, String> {
-    let var_type = match &tokens[*index] {
+fn parse_var_decl(tokens: &[SpannedToken], index: &mut usize) -> Result<Statement, Diagnostic> {
+    let start = *index;
+    let depth = parse_gyatt(tokens, index);
+    let var_type = match &tokens[*index].token {
         Token::Based => Type::Based,
         Token::SuperBased => Type::SuperBased,
         Token::Chill => Type::Chill,
         Token::Vibes => Type::Vibes,
         Token::Chad => Type::Chad,
-        _ => return Err("Expected type in variable declaration".to_string())
+        Token::NoCap => Type::NoCap,
+        Token::Rizz(n) => Type::Struct(n.clone()),
+        _ => return Err(error_at(tokens, *index, "Expected type in variable declaration"))
     };
+    let var_type = pointer_to(var_type, depth);
 
     *index += 1;
 
-    let name = match &tokens[*index] {
+    let name = match &tokens[*index].token {
         Token::Rizz(n) => n.clone(),
-        _ => return Err("Expected variable name after type".to_string())
+        _ => return Err(error_at(tokens, *index, "Expected variable name after type"))
     };
 
     *index += 1;
 
-    if !matches!(tokens[*index], Token::Slay) {
-        return Err("Expected 'slay' after variable name".to_string());
+    // 'based arr[10];' dichiara un array
+    if matches!(tokens[*index].token, Token::OpenBracket) {
+        return parse_array_decl(tokens, index, start, var_type, name);
+    }
+
+    // 'Point p;' dichiara uno struct, i campi si assegnano poi uno alla volta
+    if let Type::Struct(struct_name) = var_type {
+        if !matches!(tokens[*index].token, Token::Semicolon) {
+            return Err(missing_semicolon(tokens, *index, "Expected ';' after struct variable declaration")
+                .with_help(format!("struct fields are assigned one at a time: {}.field slay ...", name)));
+        }
+        *index += 1;
+
+        return Ok(Statement {
+            kind: StatementKind::StructVarDecl { struct_name, name, decl: None },
+            span: span_from(tokens, start, *index)
+        });
+    }
+
+    if !matches!(tokens[*index].token, Token::Slay) {
+        return Err(error_at(tokens, *index, "Expected 'slay' after variable name"));
     }
 
     *index += 1;
 
     let value = parse_expression(tokens, index)?;
 
-    if !matches!(tokens[*index], Token::Semicolon) {
-        return Err("Expected ';' after variable value".to_string());
+    if !matches!(tokens[*index].token, Token::Semicolon) {
+        return Err(missing_semicolon(tokens, *index, "Expected ';' after variable value"));
     }
 
     *index += 1;
 
-    Ok(Statement::VarDecl {var_type, name, value})
+    Ok(Statement {
+        kind: StatementKind::VarDecl {var_type, name, value, decl: None},
+        span: span_from(tokens, start, *index)
+    })
 }
 
-fn parse_assignment(tokens: &[Token], index: &mut usize) -> Result<Statement, String> {
-    let name = match &tokens[*index] {
-        Token::Rizz(n) => n.clone(),
-        _ => return Err("Expected variable name in assignment".to_string())
+// Continua parse_var_decl dopo il nome: [dimensione];
+fn parse_array_decl(tokens: &[SpannedToken], index: &mut usize, start: usize, elem_type: Type, name: String) -> Result<Statement, Diagnostic> {
+    *index += 1;  // consuma '['
+
+    let size = match &tokens[*index].token {
+        Token::IntLit(n) if *n > 0 => *n as usize,
+        _ => return Err(error_at(tokens, *index, "Expected a positive array size")
+            .with_note("array sizes must be integer literals known at compile time"))
     };
+    *index += 1;
 
+    if !matches!(tokens[*index].token, Token::CloseBracket) {
+        return Err(error_at(tokens, *index, "Expected ']' after array size"));
+    }
     *index += 1;
 
-    if !matches!(tokens[*index], Token::Slay) {
-        return Err("Expected 'slay' after variable name".to_string());
+    if !matches!(tokens[*index].token, Token::Semicolon) {
+        return Err(missing_semicolon(tokens, *index, "Expected ';' after array declaration"));
+    }
+    *index += 1;
+
+    Ok(Statement {
+        kind: StatementKind::ArrayDecl { elem_type, name, size, decl: None },
+        span: span_from(tokens, start, *index)
+    })
+}
+
+// Parse di '[indice]', usato sia per leggere che per scrivere un elemento
+fn parse_index(tokens: &[SpannedToken], index: &mut usize) -> Result<Expression, Diagnostic> {
+    *index += 1;  // consuma '['
+
+    let position = parse_expression(tokens, index)?;
+
+    if !matches!(tokens[*index].token, Token::CloseBracket) {
+        return Err(error_at(tokens, *index, "Expected ']' after index"));
+    }
+    *index += 1;
+
+    Ok(position)
+}
+
+fn parse_assignment(tokens: &[SpannedToken], index: &mut usize) -> Result<Statement, Diagnostic> {
+    let start = *index;
+    if !matches!(tokens[*index].token, Token::Rizz(_) | Token::Star | Token::OpenParen) {
+        return Err(error_at(tokens, *index, "Expected variable name in assignment"));
+    }
+
+    // a sinistra di slay può esserci una variabile ma anche arr[i], p.x, ps[i].x, *p, (*p).x ...
+    let target = parse_unary(tokens, index)?;
+    let is_lvalue = matches!(target.kind,
+        ExpressionKind::Variable{..} | ExpressionKind::Index{..} | ExpressionKind::Field{..}
+        | ExpressionKind::UnaryOp{op: UnaryOp::Deref, ..});
+    if !is_lvalue {
+        return Err(Diagnostic::error(target.span, "Invalid assignment target")
+            .with_help("only variables, elements, fields and '*pointer' can be assigned"));
+    }
+
+    if !matches!(tokens[*index].token, Token::Slay) {
+        return Err(error_at(tokens, *index, "Expected 'slay' after variable name"));
     }
 
     *index += 1;
 
     let value = parse_expression(tokens, index)?;
 
-    if !matches!(tokens[*index], Token::Semicolon) {
-        return Err("Expected ';' after variable value".to_string());
+    if !matches!(tokens[*index].token, Token::Semicolon) {
+        return Err(missing_semicolon(tokens, *index, "Expected ';' after variable value"));
     }
 
     *index += 1;
 
-    Ok(Statement::Assignment {name, value})
-}
+    let kind = match target.kind {
+        ExpressionKind::Variable { name, .. } => StatementKind::Assignment { name, value, decl: None, var_type: None },
+        _ => StatementKind::Store { target, value }
+    };
 
-fn parse_print(tokens: &[Token], index: &mut usize) -> Result<Statement, String> {
+    Ok(Statement {
+        kind,
+        span: span_from(tokens, start, *index)
+    })
+}
 
+fn parse_print(tokens: &[SpannedToken], index: &mut usize) -> Result<Statement, Diagnostic> {
+    let start = *index;
     *index += 1;
 
     let expr = parse_expression(tokens, index)?;
 
-    if !matches!(tokens[*index], Token::Semicolon) {
-        return Err("Expected ';' after print statement".to_string());
+    if !matches!(tokens[*index].token, Token::Semicolon) {
+        return Err(missing_semicolon(tokens, *index, "Expected ';' after print statement"));
     }
 
     *index += 1;
 
-    Ok(Statement::Print {expr})
+    Ok(Statement {
+        kind: StatementKind::Print {expr},
+        span: span_from(tokens, start, *index)
+    })
 }
 
-fn parse_return(tokens: &[Token], index: &mut usize) -> Result<Statement, String> {
+fn parse_return(tokens: &[SpannedToken], index: &mut usize) -> Result<Statement, Diagnostic> {
+    let start = *index;
     *index += 1;
 
-    let expr = if matches!(tokens[*index], Token::Semicolon) {
+    let expr = if matches!(tokens[*index].token, Token::Semicolon) {
         None
     } else {
         Some(parse_expression(tokens, index)?)
     };
 
-    if !matches!(tokens[*index], Token::Semicolon) {
-        return Err("Expected ';' after return statement".to_string());
+    if !matches!(tokens[*index].token, Token::Semicolon) {
+        return Err(missing_semicolon(tokens, *index, "Expected ';' after return statement"));
     }
 
     *index += 1;
 
-    Ok(Statement::Return {expr})
+    Ok(Statement {
+        kind: StatementKind::Return {expr},
+        span: span_from(tokens, start, *index)
+    })
+}
+
+// Parse chiamata: nome(arg1, arg2, ...) - il nome è già stato consumato, index punta a '('
+fn parse_call_args(tokens: &[SpannedToken], index: &mut usize) -> Result<Vec<Expression>, Diagnostic> {
+    *index += 1;  // consuma '('
+
+    let mut args = Vec::new();
+
+    // Se c'è subito ), non ci sono argomenti
+    if matches!(tokens[*index].token, Token::CloseParen) {
+        *index += 1;
+        return Ok(args);
+    }
+
+    loop {
+        args.push(parse_expression(tokens, index)?);
+
+        match &tokens[*index].token {
+            Token::Comma => {
+                *index += 1;
+            },
+            Token::CloseParen => {
+                *index += 1;
+                break;
+            },
+            _ => return Err(error_at(tokens, *index, "Expected ',' or ')' after argument"))
+        }
+    }
+
+    Ok(args)
+}
+
+// Parse chiamata come statement: nome(args);
+fn parse_call_statement(tokens: &[SpannedToken], index: &mut usize) -> Result<Statement, Diagnostic> {
+    let start = *index;
+    let name = match &tokens[*index].token {
+        Token::Rizz(n) => n.clone(),
+        _ => return Err(error_at(tokens, *index, "Expected function name in call"))
+    };
+
+    *index += 1;
+
+    let args = parse_call_args(tokens, index)?;
+
+    if !matches!(tokens[*index].token, Token::Semicolon) {
+        return Err(missing_semicolon(tokens, *index, "Expected ';' after function call"));
+    }
+
+    *index += 1;
+
+    Ok(Statement {
+        kind: StatementKind::Call { name, args, signature: None },
+        span: span_from(tokens, start, *index)
+    })
 }
 
 // ==================== PARSING IF/WHILE/FOR (implementati da me diocristo) ====================
 
 // Parse if: ong (condition) { body } [nah { else_body }]
-fn parse_if(tokens: &[Token], index: &mut usize) -> Result<Statement, String> {
+fn parse_if(tokens: &[SpannedToken], index: &mut usize, errors: &mut Vec<Diagnostic>) -> Result<Statement, Diagnostic> {
+    let start = *index;
     *index += 1;  // consuma 'ong'
 
     // Aspettati '('
-    if !matches!(tokens[*index], Token::OpenParen) {
-        return Err("Expected '(' after 'ong'".to_string());
+    if !matches!(tokens[*index].token, Token::OpenParen) {
+        return Err(error_at(tokens, *index, "Expected '(' after 'ong'"));
     }
     *index += 1;
 
@@ -216,42 +574,52 @@ fn parse_if(tokens: &[Token], index: &mut usize) -> Result<Statement, String> {
     let condition = parse_expression(tokens, index)?;
 
     // Aspettati ')'
-    if !matches!(tokens[*index], Token::CloseParen) {
-        return Err("Expected ')' after condition".to_string());
+    if !matches!(tokens[*index].token, Token::CloseParen) {
+        return Err(error_at(tokens, *index, "Expected ')' after condition"));
     }
     *index += 1;
+    let span = span_from(tokens, start, *index);
 
     // Aspettati '{'
-    if !matches!(tokens[*index], Token::OpenBrace) {
-        return Err("Expected '{' after condition".to_string());
+    if !matches!(tokens[*index].token, Token::OpenBrace) {
+        return Err(error_at(tokens, *index, "Expected '{' after condition"));
     }
     *index += 1;
 
     // Parse then body
-    let then_body = parse_body(tokens, index)?;
+    let then_body = parse_body(tokens, index, errors)?;
 
     // Consuma '}'
-    if !matches!(tokens[*index], Token::CloseBrace) {
-        return Err("Expected '}' after then body".to_string());
+    if !matches!(tokens[*index].token, Token::CloseBrace) {
+        return Err(error_at(tokens, *index, "Expected '}' after then body"));
     }
     *index += 1;
 
     // Controlla se c'è 'nah' (else)
-    let else_body = if *index < tokens.len() && matches!(tokens[*index], Token::Nah) {
+    let else_body = if *index < tokens.len() && matches!(tokens[*index].token, Token::Nah) {
         *index += 1;  // consuma 'nah'
 
+        // 'nah ong (...)' è un else-if: l'else contiene solo l'if successivo (che può avere un altro nah)
+        if matches!(tokens[*index].token, Token::Ong) {
+            let else_if = parse_if(tokens, index, errors)?;
+            return Ok(Statement {
+                kind: StatementKind::If { condition, then_body, else_body: Some(vec![else_if]) },
+                span
+            });
+        }
+
         // Aspettati '{'
-        if !matches!(tokens[*index], Token::OpenBrace) {
-            return Err("Expected '{' after 'nah'".to_string());
+        if !matches!(tokens[*index].token, Token::OpenBrace) {
+            return Err(error_at(tokens, *index, "Expected '{' or 'ong' after 'nah'"));
         }
         *index += 1;
 
         // Parse else body
-        let body = parse_body(tokens, index)?;
+        let body = parse_body(tokens, index, errors)?;
 
         // Consuma '}'
-        if !matches!(tokens[*index], Token::CloseBrace) {
-            return Err("Expected '}' after else body".to_string());
+        if !matches!(tokens[*index].token, Token::CloseBrace) {
+            return Err(error_at(tokens, *index, "Expected '}' after else body"));
         }
         *index += 1;
 
@@ -260,16 +628,20 @@ fn parse_if(tokens: &[Token], index: &mut usize) -> Result<Statement, String> {
         None
     };
 
-    Ok(Statement::If { condition, then_body, else_body })
+    Ok(Statement {
+        kind: StatementKind::If { condition, then_body, else_body },
+        span
+    })
 }
 
 // Parse while: mewing (condition) { body }
-fn parse_while(tokens: &[Token], index: &mut usize) -> Result<Statement, String> {
+fn parse_while(tokens: &[SpannedToken], index: &mut usize, errors: &mut Vec<Diagnostic>) -> Result<Statement, Diagnostic> {
+    let start = *index;
     *index += 1;  // consuma 'mewing'
 
     // Aspettati '('
-    if !matches!(tokens[*index], Token::OpenParen) {
-        return Err("Expected '(' after 'mewing'".to_string());
+    if !matches!(tokens[*index].token, Token::OpenParen) {
+        return Err(error_at(tokens, *index, "Expected '(' after 'mewing'"));
     }
     *index += 1;
 
@@ -277,119 +649,214 @@ fn parse_while(tokens: &[Token], index: &mut usize) -> Result<Statement, String>
     let condition = parse_expression(tokens, index)?;
 
     // Aspettati ')'
-    if !matches!(tokens[*index], Token::CloseParen) {
-        return Err("Expected ')' after condition".to_string());
+    if !matches!(tokens[*index].token, Token::CloseParen) {
+        return Err(error_at(tokens, *index, "Expected ')' after condition"));
     }
     *index += 1;
+    let span = span_from(tokens, start, *index);
 
     // Aspettati '{'
-    if !matches!(tokens[*index], Token::OpenBrace) {
-        return Err("Expected '{' after condition".to_string());
+    if !matches!(tokens[*index].token, Token::OpenBrace) {
+        return Err(error_at(tokens, *index, "Expected '{' after condition"));
     }
     *index += 1;
 
     // Parse body
-    let body = parse_body(tokens, index)?;
+    let body = parse_body(tokens, index, errors)?;
 
     // Consuma '}'
-    if !matches!(tokens[*index], Token::CloseBrace) {
-        return Err("Expected '}' after while body".to_string());
+    if !matches!(tokens[*index].token, Token::CloseBrace) {
+        return Err(error_at(tokens, *index, "Expected '}' after while body"));
     }
     *index += 1;
 
-    Ok(Statement::While { condition, body })
+    Ok(Statement {
+        kind: StatementKind::While { condition, body },
+        span
+    })
 }
 
 // Parse for: sixSeven (init; condition; increment) { body }
-fn parse_for(tokens: &[Token], index: &mut usize) -> Result<Statement, String> {
+fn parse_for(tokens: &[SpannedToken], index: &mut usize, errors: &mut Vec<Diagnostic>) -> Result<Statement, Diagnostic> {
+    let start = *index;
     *index += 1;  // consuma 'sixSeven'
 
     // Aspettati '('
-    if !matches!(tokens[*index], Token::OpenParen) {
-        return Err("Expected '(' after 'sixSeven'".to_string());
+    if !matches!(tokens[*index].token, Token::OpenParen) {
+        return Err(error_at(tokens, *index, "Expected '(' after 'sixSeven'"));
     }
     *index += 1;
 
     // Parse init (deve essere VarDecl o Assignment)
-    let init = match &tokens[*index] {
-        Token::Based | Token::SuperBased | Token::Chill | Token::Vibes | Token::Chad => {
+    let init = match &tokens[*index].token {
+        Token::Based | Token::SuperBased | Token::Chill | Token::Vibes | Token::Chad | Token::NoCap | Token::Gyatt => {
             parse_var_decl(tokens, index)?
         },
         Token::Rizz(_) => parse_assignment(tokens, index)?,
-        _ => return Err("Expected variable declaration or assignment in for init".to_string())
+        _ => return Err(error_at(tokens, *index, "Expected variable declaration or assignment in for init"))
     };
 
     // Ora aspettati la condition
     let condition = parse_expression(tokens, index)?;
 
     // Aspettati ';'
-    if !matches!(tokens[*index], Token::Semicolon) {
-        return Err("Expected ';' after for condition".to_string());
+    if !matches!(tokens[*index].token, Token::Semicolon) {
+        return Err(missing_semicolon(tokens, *index, "Expected ';' after for condition"));
     }
     *index += 1;
 
     // Parse increment (deve essere Assignment, ma SENZA il ';' finale)
-    let increment = if let Token::Rizz(name) = &tokens[*index] {
+    let increment_start = *index;
+    let increment = if let Token::Rizz(name) = &tokens[*index].token {
         let var_name = name.clone();
         *index += 1;
 
         // Aspettati 'slay'
-        if !matches!(tokens[*index], Token::Slay) {
-            return Err("Expected 'slay' in for increment".to_string());
+        if !matches!(tokens[*index].token, Token::Slay) {
+            return Err(error_at(tokens, *index, "Expected 'slay' in for increment"));
         }
         *index += 1;
 
         // Parse expression
         let value = parse_expression(tokens, index)?;
 
-        Statement::Assignment {
-            name: var_name,
-            value
+        Statement {
+            kind: StatementKind::Assignment {
+                name: var_name,
+                value,
+                decl: None,
+                var_type: None
+            },
+            span: span_from(tokens, increment_start, *index)
         }
     } else {
-        return Err("Expected assignment in for increment".to_string());
+        return Err(error_at(tokens, *index, "Expected assignment in for increment"));
     };
 
     // Aspettati ')'
-    if !matches!(tokens[*index], Token::CloseParen) {
-        return Err("Expected ')' after for increment".to_string());
+    if !matches!(tokens[*index].token, Token::CloseParen) {
+        return Err(error_at(tokens, *index, "Expected ')' after for increment"));
     }
     *index += 1;
+    let span = span_from(tokens, start, *index);
 
     // Aspettati '{'
-    if !matches!(tokens[*index], Token::OpenBrace) {
-        return Err("Expected '{' after for header".to_string());
+    if !matches!(tokens[*index].token, Token::OpenBrace) {
+        return Err(error_at(tokens, *index, "Expected '{' after for header"));
     }
     *index += 1;
 
     // Parse body
-    let body = parse_body(tokens, index)?;
+    let body = parse_body(tokens, index, errors)?;
 
     // Consuma '}'
-    if !matches!(tokens[*index], Token::CloseBrace) {
-        return Err("Expected '}' after for body".to_string());
+    if !matches!(tokens[*index].token, Token::CloseBrace) {
+        return Err(error_at(tokens, *index, "Expected '}' after for body"));
     }
     *index += 1;
 
-    Ok(Statement::For {
-        init: Box::new(init),
-        condition,
-        increment: Box::new(increment),
-        body
+    Ok(Statement {
+        kind: StatementKind::For {
+            init: Box::new(init),
+            condition,
+            increment: Box::new(increment),
+            body
+        },
+        span
     })
 }
 
+// Costruisce un BinOp che copre left e right
+fn make_binop(left: Expression, op: BinOp, right: Expression) -> Expression {
+    let span = left.span.to(right.span);
+    Expression {
+        kind: ExpressionKind::BinOp {
+            left: Box::new(left),
+            op,
+            right: Box::new(right)
+        },
+        span,
+        resolved_type: None
+    }
+}
+
 // Entry point per le espressioni - gestisce la precedenza più bassa
-fn parse_expression(tokens: &[Token], index: &mut usize) -> Result<Expression, String> {
-    parse_comparison(tokens, index)
+fn parse_expression(tokens: &[SpannedToken], index: &mut usize) -> Result<Expression, Diagnostic> {
+    parse_or(tokens, index)
 }
 
-// Livello 1: Comparazione (==, !=, <, >, <=, >=)
-fn parse_comparison(tokens: &[Token], index: &mut usize) -> Result<Expression, String> {
-    let mut left = parse_additive(tokens, index)?;
+// Livello 1: Or logico (||)
+fn parse_or(tokens: &[SpannedToken], index: &mut usize) -> Result<Expression, Diagnostic> {
+    let mut left = parse_and(tokens, index)?;
+
+    while matches!(tokens[*index].token, Token::Or) {
+        *index += 1;
+        let right = parse_and(tokens, index)?;
+        left = make_binop(left, BinOp::Or, right);
+    }
+
+    Ok(left)
+}
+
+// Livello 2: And logico (&&), lega più forte di ||
+fn parse_and(tokens: &[SpannedToken], index: &mut usize) -> Result<Expression, Diagnostic> {
+    let mut left = parse_bit_or(tokens, index)?;
+
+    while matches!(tokens[*index].token, Token::And) {
+        *index += 1;
+        let right = parse_bit_or(tokens, index)?;
+        left = make_binop(left, BinOp::And, right);
+    }
+
+    Ok(left)
+}
+
+// Livello 3: Or bit a bit (|)
+// Come in C gli operatori bit a bit stanno sotto ai confronti: x & 1 == 0 è x & (1 == 0)
+fn parse_bit_or(tokens: &[SpannedToken], index: &mut usize) -> Result<Expression, Diagnostic> {
+    let mut left = parse_bit_xor(tokens, index)?;
+
+    while matches!(tokens[*index].token, Token::Pipe) {
+        *index += 1;
+        let right = parse_bit_xor(tokens, index)?;
+        left = make_binop(left, BinOp::BitOr, right);
+    }
+
+    Ok(left)
+}
+
+// Livello 4: Xor bit a bit (^)
+fn parse_bit_xor(tokens: &[SpannedToken], index: &mut usize) -> Result<Expression, Diagnostic> {
+    let mut left = parse_bit_and(tokens, index)?;
+
+    while matches!(tokens[*index].token, Token::Caret) {
+        *index += 1;
+        let right = parse_bit_and(tokens, index)?;
+        left = make_binop(left, BinOp::BitXor, right);
+    }
+
+    Ok(left)
+}
+
+// Livello 5: And bit a bit (&)
+fn parse_bit_and(tokens: &[SpannedToken], index: &mut usize) -> Result<Expression, Diagnostic> {
+    let mut left = parse_comparison(tokens, index)?;
+
+    while matches!(tokens[*index].token, Token::Ampersand) {
+        *index += 1;
+        let right = parse_comparison(tokens, index)?;
+        left = make_binop(left, BinOp::BitAnd, right);
+    }
+
+    Ok(left)
+}
+
+// Livello 6: Comparazione (==, !=, <, >, <=, >=)
+fn parse_comparison(tokens: &[SpannedToken], index: &mut usize) -> Result<Expression, Diagnostic> {
+    let mut left = parse_shift(tokens, index)?;
 
     while *index < tokens.len() {
-        let op = match &tokens[*index] {
+        let op = match &tokens[*index].token {
             Token::Equal => BinOp::Equal,
             Token::NotEqual => BinOp::NotEqual,
             Token::Less => BinOp::Less,
@@ -400,23 +867,38 @@ fn parse_comparison(tokens: &[Token], index: &mut usize) -> Result<Expression, S
         };
 
         *index += 1;
-        let right = parse_additive(tokens, index)?;
-        left = Expression::BinOp {
-            left: Box::new(left),
-            op,
-            right: Box::new(right)
+        let right = parse_shift(tokens, index)?;
+        left = make_binop(left, op, right);
+    }
+
+    Ok(left)
+}
+
+// Livello 7: Shift (<<, >>)
+fn parse_shift(tokens: &[SpannedToken], index: &mut usize) -> Result<Expression, Diagnostic> {
+    let mut left = parse_additive(tokens, index)?;
+
+    while *index < tokens.len() {
+        let op = match &tokens[*index].token {
+            Token::ShiftLeft => BinOp::Shl,
+            Token::ShiftRight => BinOp::Shr,
+            _ => break
         };
+
+        *index += 1;
+        let right = parse_additive(tokens, index)?;
+        left = make_binop(left, op, right);
     }
 
     Ok(left)
 }
 
-// Livello 2: Addizione/Sottrazione (+, -)
-fn parse_additive(tokens: &[Token], index: &mut usize) -> Result<Expression, String> {
+// Livello 8: Addizione/Sottrazione (+, -)
+fn parse_additive(tokens: &[SpannedToken], index: &mut usize) -> Result<Expression, Diagnostic> {
     let mut left = parse_multiplicative(tokens, index)?;
 
     while *index < tokens.len() {
-        let op = match &tokens[*index] {
+        let op = match &tokens[*index].token {
             Token::Plus => BinOp::Add,
             Token::Minus => BinOp::Sub,
             _ => break
@@ -424,122 +906,298 @@ fn parse_additive(tokens: &[Token], index: &mut usize) -> Result<Expression, Str
 
         *index += 1;
         let right = parse_multiplicative(tokens, index)?;
-        left = Expression::BinOp {
-            left: Box::new(left),
-            op,
-            right: Box::new(right)
-        };
+        left = make_binop(left, op, right);
     }
 
     Ok(left)
 }
 
-// Livello 3: Moltiplicazione/Divisione (*, /)
-fn parse_multiplicative(tokens: &[Token], index: &mut usize) -> Result<Expression, String> {
-    let mut left = parse_primary(tokens, index)?;
+// Livello 9: Moltiplicazione/Divisione/Modulo (*, /, %)
+fn parse_multiplicative(tokens: &[SpannedToken], index: &mut usize) -> Result<Expression, Diagnostic> {
+    let mut left = parse_cast(tokens, index)?;
 
     while *index < tokens.len() {
-        let op = match &tokens[*index] {
+        let op = match &tokens[*index].token {
             Token::Star => BinOp::Mul,
             Token::Slash => BinOp::Div,
+            Token::Percent => BinOp::Mod,
             _ => break
         };
 
         *index += 1;
-        let right = parse_primary(tokens, index)?;
-        left = Expression::BinOp {
-            left: Box::new(left),
-            op,
-            right: Box::new(right)
-        };
+        let right = parse_cast(tokens, index)?;
+        left = make_binop(left, op, right);
     }
 
     Ok(left)
 }
 
-// Livello 4: Primari (numeri, variabili, parentesi)
-fn parse_primary(tokens: &[Token], index: &mut usize) -> Result<Expression, String> {
-    let expr = match &tokens[*index] {
+// Livello 10: Cast (x as based), lega più forte di * ma meno degli unari: -x as chill è (-x) as chill
+fn parse_cast(tokens: &[SpannedToken], index: &mut usize) -> Result<Expression, Diagnostic> {
+    let start = *index;
+    let mut expr = parse_unary(tokens, index)?;
+
+    while matches!(tokens[*index].token, Token::As) {
+        *index += 1;
+        // anche verso un puntatore: s as gyatt chad
+        let depth = parse_gyatt(tokens, index);
+        let target = match &tokens[*index].token {
+            Token::Based => Type::Based,
+            Token::SuperBased => Type::SuperBased,
+            Token::Chill => Type::Chill,
+            Token::Vibes => Type::Vibes,
+            Token::Chad => Type::Chad,
+            Token::NoCap => Type::NoCap,
+            _ => return Err(error_at(tokens, *index, "Expected type after 'as'"))
+        };
+        let target = pointer_to(target, depth);
+        *index += 1;
+
+        expr = Expression {
+            kind: ExpressionKind::Cast { expr: Box::new(expr), target },
+            span: span_from(tokens, start, *index),
+            resolved_type: None
+        };
+    }
+
+    Ok(expr)
+}
+
+// Livello 11: Operatori unari (-, !, ~, &, *), si possono concatenare tipo !!x, - -x o **pp
+fn parse_unary(tokens: &[SpannedToken], index: &mut usize) -> Result<Expression, Diagnostic> {
+    let op = match &tokens[*index].token {
+        Token::Minus => UnaryOp::Neg,
+        Token::Not => UnaryOp::Not,
+        Token::Tilde => UnaryOp::BitNot,
+        Token::Ampersand => UnaryOp::AddressOf,
+        Token::Star => UnaryOp::Deref,
+        _ => return parse_postfix(tokens, index)
+    };
+
+    let start = *index;
+    *index += 1;
+    let operand = parse_unary(tokens, index)?;
+    let span = span_from(tokens, start, *index);
+
+    // -letterale diventa direttamente un letterale negativo: così -2147483648 è un based
+    // (2147483648 da solo non ci sta in un i32 e sarebbe un superBased)
+    if let UnaryOp::Neg = op {
+        if let ExpressionKind::Float(f) = operand.kind {
+            return Ok(Expression { kind: ExpressionKind::Float(-f), span, resolved_type: None });
+        }
+
+        let folded = match operand.kind {
+            ExpressionKind::Integer(n) => Some(-(n as i64)),
+            ExpressionKind::Long(n) => n.checked_neg(),
+            _ => None
+        };
+
+        if let Some(n) = folded {
+            let kind = if n >= i32::MIN as i64 && n <= i32::MAX as i64 {
+                ExpressionKind::Integer(n as i32)
+            } else {
+                ExpressionKind::Long(n)
+            };
+            return Ok(Expression { kind, span, resolved_type: None });
+        }
+    }
+
+    Ok(Expression {
+        kind: ExpressionKind::UnaryOp { op, operand: Box::new(operand) },
+        span,
+        resolved_type: None
+    })
+}
+
+// Livello 12: Postfix, accesso a elementi e campi concatenabili (arr[i], p.x, ps[i].pos.x)
+fn parse_postfix(tokens: &[SpannedToken], index: &mut usize) -> Result<Expression, Diagnostic> {
+    let start = *index;
+    let mut expr = parse_primary(tokens, index)?;
+
+    loop {
+        let kind = match &tokens[*index].token {
+            Token::OpenBracket => {
+                let position = parse_index(tokens, index)?;
+                ExpressionKind::Index { array: Box::new(expr), index: Box::new(position) }
+            },
+            Token::Dot => {
+                *index += 1;
+                let field = match &tokens[*index].token {
+                    Token::Rizz(n) => n.clone(),
+                    _ => return Err(error_at(tokens, *index, "Expected field name after '.'"))
+                };
+                *index += 1;
+                ExpressionKind::Field { base: Box::new(expr), field }
+            },
+            _ => break
+        };
+
+        expr = Expression { kind, span: span_from(tokens, start, *index), resolved_type: None };
+    }
+
+    Ok(expr)
+}
+
+// Livello 13: Primari (numeri, variabili, parentesi)
+fn parse_primary(tokens: &[SpannedToken], index: &mut usize) -> Result<Expression, Diagnostic> {
+    let span = tokens[*index].span;
+    let kind = match &tokens[*index].token {
         Token::IntLit(n) => {
             if *n >= i32::MIN as i64 && *n <= i32::MAX as i64 {
-                Expression::Integer(*n as i32)
+                ExpressionKind::Integer(*n as i32)
             } else {
-                Expression::Long(*n)
+                ExpressionKind::Long(*n)
+            }
+        }
+        Token::FloatLit(f) => ExpressionKind::Float(*f),
+        Token::StringLit(s) => ExpressionKind::StringLit(s.clone()),
+        Token::CharLit(c) => ExpressionKind::CharLit(*c),
+        Token::Fr => ExpressionKind::Bool(true),
+        Token::Cap => ExpressionKind::Bool(false),
+        Token::Ghosted => ExpressionKind::Null,
+        Token::Rizz(name) => {
+            // Se dopo il nome c'è '(' è una chiamata a funzione
+            if matches!(tokens.get(*index + 1).map(|t| &t.token), Some(Token::OpenParen)) {
+                let start = *index;
+                let name = name.clone();
+                *index += 1;
+                let args = parse_call_args(tokens, index)?;
+                return Ok(Expression {
+                    kind: ExpressionKind::Call { name, args, signature: None },
+                    span: span_from(tokens, start, *index),
+                    resolved_type: None
+                });
             }
+
+            ExpressionKind::Variable { name: name.clone(), decl: None }
         }
-        Token::StringLit(s) => Expression::StringLit(s.clone()),
-        Token::CharLit(c) => Expression::CharLit(*c),
-        Token::Rizz(name) => Expression::Variable(name.clone()),
 
         // Gestione parentesi: (2 + 3) * 4
         Token::OpenParen => {
             *index += 1;
             let expr = parse_expression(tokens, index)?;
-            if !matches!(tokens[*index], Token::CloseParen) {
-                return Err("Expected ')' after expression".to_string());
+            if !matches!(tokens[*index].token, Token::CloseParen) {
+                return Err(error_at(tokens, *index, "Expected ')' after expression"));
             }
             *index += 1;
             return Ok(expr);
         }
 
-        _ => return Err(format!("Expected expression, found {:?}", tokens[*index]))
+        other => return Err(error_at(tokens, *index, &format!("Expected expression, found {:?}", other)))
     };
 
     *index += 1;
-    Ok(expr)
+    Ok(Expression { kind, span, resolved_type: None })
 }
 
 // ==================== PARSING DEL BODY ====================
 
-fn parse_body(tokens: &[Token], index: &mut usize) -> Result<Vec<Statement>, String> {
+fn parse_body(tokens: &[SpannedToken], index: &mut usize, errors: &mut Vec<Diagnostic>) -> Result<Vec<Statement>, Diagnostic> {
     let mut statements = Vec::new();
 
     // Loop finché non incontriamo "}"
-    while !matches!(tokens[*index], Token::CloseBrace) {
-        // Guarda che token è e decidi cosa fare (implementato da me sto cazzone di parser)
-        let stmt = match &tokens[*index] {
-            Token::Based | Token::SuperBased | Token::Chill | Token::Vibes | Token::Chad => parse_var_decl(tokens, index)?,
-            Token::Flex => parse_print(tokens, index)?,
-            Token::Ohio => {
-                *index += 1;
-                if !matches!(tokens[*index], Token::Semicolon) {
-                    return Err("Expected ';' after 'ohio'".to_string());
-                }
-                *index += 1;
-                Statement::Break
+    while !matches!(tokens[*index].token, Token::CloseBrace) {
+        match parse_statement(tokens, index, errors) {
+            Ok(stmt) => statements.push(stmt),
+            // se siamo finiti su 'bussin'/'skibidi' o a fine file il blocco non si chiuderà mai: lascio decidere al chiamante
+            Err(err) if matches!(tokens[*index].token, Token::Bussin | Token::Skibidi | Token::Eof) => return Err(err),
+            Err(err) => {
+                // registro l'errore e riparto dallo statement successivo
+                errors.push(err);
+                synchronize(tokens, index);
             }
-            Token::Yeet => parse_return(tokens, index)?,
-            Token::Ong => parse_if(tokens, index)?,         // if (implementato da me diocane)
-            Token::Mewing => parse_while(tokens, index)?,   // while (anche questo l'ho fatto io)
-            Token::SixSeven => parse_for(tokens, index)?,   // for (pure questo è roba mia)
-            Token::Rizz(_) => parse_assignment(tokens, index)?,
-            _ => return Err(format!("Unexpected token in body: {:?}", tokens[*index]))
-        };
-
-        statements.push(stmt);
+        }
     }
 
     Ok(statements)
 }
 
-pub fn parse(tokens: Vec<Token>) -> Result<Program, String> {
+fn parse_statement(tokens: &[SpannedToken], index: &mut usize, errors: &mut Vec<Diagnostic>) -> Result<Statement, Diagnostic> {
+    // Guarda che token è e decidi cosa fare (implementato da me sto cazzone di parser)
+    let stmt = match &tokens[*index].token {
+        Token::Based | Token::SuperBased | Token::Chill | Token::Vibes | Token::Chad | Token::NoCap | Token::Gyatt => parse_var_decl(tokens, index)?,
+        Token::Flex => parse_print(tokens, index)?,
+        Token::Ohio => {
+            let start = *index;
+            *index += 1;
+            if !matches!(tokens[*index].token, Token::Semicolon) {
+                return Err(missing_semicolon(tokens, *index, "Expected ';' after 'ohio'"));
+            }
+            *index += 1;
+            Statement { kind: StatementKind::Break, span: span_from(tokens, start, *index) }
+        }
+        Token::Fax => {
+            let start = *index;
+            *index += 1;
+            if !matches!(tokens[*index].token, Token::Semicolon) {
+                return Err(missing_semicolon(tokens, *index, "Expected ';' after 'fax'"));
+            }
+            *index += 1;
+            Statement { kind: StatementKind::Continue, span: span_from(tokens, start, *index) }
+        }
+        Token::Yeet => parse_return(tokens, index)?,
+        Token::Ong => parse_if(tokens, index, errors)?,         // if (implementato da me diocane)
+        Token::Mewing => parse_while(tokens, index, errors)?,   // while (anche questo l'ho fatto io)
+        Token::SixSeven => parse_for(tokens, index, errors)?,   // for (pure questo è roba mia)
+        // 'Point p;': un nome di tipo seguito dal nome della variabile
+        Token::Rizz(_) if matches!(tokens.get(*index + 1).map(|t| &t.token), Some(Token::Rizz(_))) => parse_var_decl(tokens, index)?,
+        Token::Rizz(_) if matches!(tokens.get(*index + 1).map(|t| &t.token), Some(Token::OpenParen)) => parse_call_statement(tokens, index)?,
+        Token::Rizz(_) | Token::Star | Token::OpenParen => parse_assignment(tokens, index)?,
+        Token::Eof => return Err(error_at(tokens, *index, "Unexpected end of file: missing '}'")),
+        Token::Bussin => return Err(error_at(tokens, *index, "Expected '}' before the next 'bussin'")),
+        Token::Skibidi => return Err(error_at(tokens, *index, "Expected '}' before the next 'skibidi'")),
+        Token::LockedIn => return Err(error_at(tokens, *index, "Constants can only be declared at top level")
+            .with_help("move the 'lockedIn' declaration outside the function")),
+        other => return Err(error_at(tokens, *index, &format!("Unexpected token in body: {:?}", other)))
+    };
+
+    Ok(stmt)
+}
+
+// Ritorna tutti gli errori trovati, non solo il primo
+pub fn parse(tokens: Vec<SpannedToken>) -> Result<Program, Vec<Diagnostic>> {
     let mut index = 0;
+    let mut structs = Vec::new();
+    let mut globals = Vec::new();
     let mut functions = Vec::new();
+    let mut errors = Vec::new();
+
+    while tokens[index].token != Token::Eof {
+        let start = index;
+        let result = match tokens[index].token {
+            Token::Bussin => parse_function(&tokens, &mut index, &mut errors).map(|func| functions.push(func)),
+            Token::Skibidi => parse_struct_def(&tokens, &mut index).map(|def| structs.push(def)),
+            ref token if starts_global(token) => {
+                match parse_global(&tokens, &mut index) {
+                    Ok(global) => globals.push(global),
+                    // una globale sbagliata non si trascina dietro quelle dopo: riparto dal ';'
+                    Err(err) => {
+                        errors.push(err);
+                        synchronize(&tokens, &mut index);
+                    }
+                }
+                continue;
+            },
+            _ => Err(error_at(&tokens, index, "Expected function, struct or global declaration"))
+        };
 
-    while index < tokens.len() {
-        if tokens[index] == Token::Bussin {
-            let func = parse_function(&tokens, &mut index);
-            functions.push(func?);
-        } else {
-            return Err("Expected function declaration".to_string());
+        // se qualcosa è andato storto riparto dalla prossima dichiarazione
+        if let Err(err) = result {
+            errors.push(err);
+            skip_to_declaration(&tokens, start, &mut index);
         }
     }
 
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+
     //controlliamo se c'è almeno un main
     let has_main = functions.iter().any(|f| f.name == "sigma");
     if !has_main {
-        return Err("Program must have at least one 'bussin sigma()' function".to_string());
+        return Err(vec![Diagnostic::global("Program must have at least one 'bussin sigma()' function")
+            .with_help("add an entry point: bussin ghost sigma() { ... }")]);
     }
 
-    Ok(Program { functions })
-}
\ No newline at end of file
+    Ok(Program { structs, globals, functions })
+}
@@ -1,3 +1,4 @@
+#[allow(clippy::module_inception)]
 pub mod semantic;
 
 pub use semantic::analyze_program;
\ No newline at end of file
@@ -1,16 +1,37 @@
 use std::collections::HashMap;
+use crate::builtins::{builtin, Builtin};
+use crate::error::Diagnostic;
+use crate::lexer::Span;
 use crate::parser::ast::*;
 
+// Registri a0..a7 (e fa0..fa7 per i chill) disponibili per i parametri; un superBased ne usa due
+const MAX_PARAMETERS: usize = 8;
+
 struct SymbolTable {
-    scopes: Vec<HashMap<String, SymbolInfo>>
+    scopes: Vec<HashMap<String, SymbolInfo>>,
+    functions: HashMap<String, FunctionInfo>,
+    structs: HashMap<String, StructInfo>,
+    next_decl: DeclId,             // id della prossima dichiarazione
+    loop_depth: usize,             // quanti mewing/sixSeven stanno intorno allo statement corrente
+    diagnostics: Vec<Diagnostic>   // errori, nell'ordine in cui li troviamo
 }
 
 impl SymbolTable {
     fn new() -> Self {
         Self {
-            scopes: vec![HashMap::new()]
+            scopes: vec![HashMap::new()],
+            functions: HashMap::new(),
+            structs: HashMap::new(),
+            next_decl: 0,
+            loop_depth: 0,
+            diagnostics: Vec::new()
         }
     }
+
+    // Registra un errore e va avanti con l'analisi
+    fn report(&mut self, diagnostic: Diagnostic) {
+        self.diagnostics.push(diagnostic);
+    }
     fn enter_scope(&mut self) {
         self.scopes.push(HashMap::new());
     }
@@ -18,16 +39,30 @@ impl SymbolTable {
         self.scopes.pop();
     }
 
-    fn declare(&mut self, name: String, tipo: Type, location: usize) -> Result<(), String> {
+    // Ritorna l'id della nuova dichiarazione, che il codegen usa al posto del nome
+    fn declare(&mut self, name: String, tipo: Type, span: Span) -> Result<DeclId, Diagnostic> {
+        self.declare_symbol(name, tipo, span, false)
+    }
+
+    // Costante lockedIn: si legge come una variabile ma non si riassegna
+    fn declare_constant(&mut self, name: String, tipo: Type, span: Span) -> Result<DeclId, Diagnostic> {
+        self.declare_symbol(name, tipo, span, true)
+    }
+
+    fn declare_symbol(&mut self, name: String, tipo: Type, span: Span, constant: bool) -> Result<DeclId, Diagnostic> {
         let current_scope = self.scopes.last_mut().unwrap();
 
-        if current_scope.contains_key(&name) {
-            return Err(format!("Variable '{}' already declared in this scope", name));
+        if let Some(previous) = current_scope.get(&name) {
+            return Err(Diagnostic::error(span, format!("Variable '{}' already declared in this scope", name))
+                .with_label(previous.span, "previous declaration here")
+                .with_help("use a different name, or assign with 'slay' instead of redeclaring"));
         }
 
-        current_scope.insert(name.clone(), SymbolInfo { name, tipo, location});
+        let decl = self.next_decl;
+        self.next_decl += 1;
+        current_scope.insert(name, SymbolInfo { tipo, span, decl, constant });
 
-        Ok(())
+        Ok(decl)
     }
 
     fn lookup(&self, name: &str) -> Option<&SymbolInfo> {
@@ -38,192 +73,715 @@ impl SymbolTable {
         }
         None
     }
+
+    fn declare_function(&mut self, func: &Function) -> Result<(), Diagnostic> {
+        if builtin(&func.name).is_some() {
+            return Err(Diagnostic::error(func.span, format!("Function '{}' is already a builtin", func.name))
+                .with_help("give your function a different name"));
+        }
+
+        if let Some(previous) = self.functions.get(&func.name) {
+            return Err(Diagnostic::error(func.span, format!("Function '{}' already declared", func.name))
+                .with_label(previous.span, "previous declaration here"));
+        }
+
+        let info = FunctionInfo {
+            return_type: func.return_type.clone(),
+            parameters: func.parameters.iter().map(|p| p.parameter_type.clone()).collect(),
+            span: func.span
+        };
+        self.functions.insert(func.name.clone(), info);
+
+        Ok(())
+    }
+
+    fn lookup_function(&self, name: &str) -> Option<&FunctionInfo> {
+        self.functions.get(name)
+    }
+
+    fn declare_struct(&mut self, def: &StructDef) -> Result<(), Diagnostic> {
+        if let Some(previous) = self.structs.get(&def.name) {
+            return Err(Diagnostic::error(def.span, format!("Struct '{}' already declared", def.name))
+                .with_label(previous.span, "previous declaration here"));
+        }
+
+        let info = StructInfo {
+            fields: def.fields.iter().map(|f| (f.name.clone(), f.field_type.clone())).collect(),
+            span: def.span
+        };
+        self.structs.insert(def.name.clone(), info);
+
+        Ok(())
+    }
+
+    fn lookup_struct(&self, name: &str) -> Option<&StructInfo> {
+        self.structs.get(name)
+    }
 }
 
 struct SymbolInfo {
-    name: String,
     tipo: Type,
-    location: usize
+    span: Span,  // dove è stata dichiarata
+    decl: DeclId,
+    constant: bool  // lockedIn
 }
 
-impl SymbolInfo {
-    fn new(name: String, tipo: Type, location: usize) -> Self {
-        return SymbolInfo {name, tipo, location}
-    }
+// firma di una funzione bussin, serve per controllare le chiamate
+struct FunctionInfo {
+    return_type: Type,
+    parameters: Vec<Type>,
+    span: Span
 }
 
-pub fn analyze_program(ast: &Program) -> Result<(), String> {
+// campi di uno struct skibidi, nell'ordine di dichiarazione
+struct StructInfo {
+    fields: Vec<(String, Type)>,
+    span: Span
+}
+
+// In caso di errore ritorna tutti i diagnostic raccolti, non solo il primo
+pub fn analyze_program(ast: &mut Program) -> Result<(), Vec<Diagnostic>> {
     let mut stm_table = SymbolTable::new();
-    // scrivere l'analisi semantica
+
+    // gli struct per primi, così i tipi sono noti ovunque (anche negli altri struct)
+    for def in &ast.structs {
+        if let Err(err) = stm_table.declare_struct(def) {
+            stm_table.report(err);
+        }
+    }
+    for def in &ast.structs {
+        if let Err(err) = analyze_struct(&stm_table, def) {
+            stm_table.report(err);
+        }
+    }
+
+    // le globali finiscono nello scope più esterno: ogni funzione le vede, ma i suoi
+    // parametri e le sue variabili vengono cercati prima e possono nasconderle
+    for global in &mut ast.globals {
+        if let Err(err) = analyze_global(&mut stm_table, global) {
+            stm_table.report(err);
+        }
+    }
+
+    // poi registro tutte le firme, così si può chiamare una funzione dichiarata più sotto
     for func in &ast.functions {
-        analyze_function(&mut stm_table, func)?;
+        let registers: usize = func.parameters.iter()
+            .map(|p| match p.parameter_type {
+                Type::SuperBased => 2,
+                Type::Chill => 0,
+                _ => 1
+            })
+            .sum();
+        let float_registers = func.parameters.iter().filter(|p| p.parameter_type == Type::Chill).count();
+        if registers > MAX_PARAMETERS || float_registers > MAX_PARAMETERS {
+            stm_table.report(Diagnostic::error(func.span, format!("Function '{}' has {} parameters, at most {} registers are supported", func.name, func.parameters.len(), MAX_PARAMETERS))
+                .with_note("parameters are passed in registers a0..a7 (fa0..fa7 for chill), a superBased takes two of them"));
+        }
+        if let Err(err) = stm_table.declare_function(func) {
+            stm_table.report(err);
+        }
+    }
+
+    for func in &mut ast.functions {
+        analyze_function(&mut stm_table, func);
+    }
+
+    if !stm_table.diagnostics.is_empty() {
+        return Err(stm_table.diagnostics);
+    }
+
+    Ok(())
+}
+
+// Controlla i campi di uno struct: tipi esistenti, nomi non duplicati e niente struct che contiene se stesso
+fn analyze_struct(stm_tab: &SymbolTable, def: &StructDef) -> Result<(), Diagnostic> {
+    for (i, field) in def.fields.iter().enumerate() {
+        if let Some(previous) = def.fields[..i].iter().find(|f| f.name == field.name) {
+            return Err(Diagnostic::error(field.span, format!("Field '{}' already declared in struct '{}'", field.name, def.name))
+                .with_label(previous.span, "previous declaration here"));
+        }
+
+        check_type_exists(stm_tab, &field.field_type, field.span)?;
+
+        if contains_struct(stm_tab, &field.field_type, &def.name, &mut Vec::new()) {
+            return Err(Diagnostic::error(field.span, format!("Struct '{}' contains itself through field '{}'", def.name, field.name))
+                .with_note("a struct stored by value inside itself would have infinite size"));
+        }
+    }
+
+    Ok(())
+}
+
+// true se un valore di tipo `tipo` contiene (anche indirettamente) lo struct `target`
+fn contains_struct(stm_tab: &SymbolTable, tipo: &Type, target: &str, visited: &mut Vec<String>) -> bool {
+    match tipo {
+        Type::Array(elem, _) => contains_struct(stm_tab, elem, target, visited),
+        Type::Struct(name) => {
+            if name == target {
+                return true;
+            }
+            if visited.contains(name) {
+                return false;
+            }
+            visited.push(name.clone());
+
+            match stm_tab.lookup_struct(name) {
+                Some(info) => info.fields.iter().any(|(_, t)| contains_struct(stm_tab, t, target, visited)),
+                None => false
+            }
+        },
+        _ => false
+    }
+}
+
+// Un tipo struct deve essere stato dichiarato con skibidi
+fn check_type_exists(stm_tab: &SymbolTable, tipo: &Type, span: Span) -> Result<(), Diagnostic> {
+    match tipo {
+        Type::Array(elem, _) | Type::Pointer(elem) => check_type_exists(stm_tab, elem, span),
+        Type::Struct(name) if stm_tab.lookup_struct(name).is_none() => {
+            Err(Diagnostic::error(span, format!("Unknown type '{}'", name))
+                .with_help(format!("declare it with: skibidi {} {{ ... }}", name)))
+        },
+        _ => Ok(())
+    }
+}
+
+// Il valore iniziale di una globale viene scritto in .data/.rodata già in compilazione,
+// quindi deve essere un letterale (al massimo con il meno davanti)
+fn analyze_global(stm_tab: &mut SymbolTable, global: &mut Global) -> Result<(), Diagnostic> {
+    check_type_exists(stm_tab, &global.var_type, global.span)?;
+
+    // la dichiaro comunque, così un valore sbagliato non si porta dietro
+    // una valanga di "not declared" nelle funzioni
+    let decl = if global.constant {
+        stm_tab.declare_constant(global.name.clone(), global.var_type.clone(), global.span)?
+    } else {
+        stm_tab.declare(global.name.clone(), global.var_type.clone(), global.span)?
+    };
+    global.decl = Some(decl);
+
+    if !is_literal(&global.value) {
+        return Err(Diagnostic::error(global.value.span, format!("Initial value of global '{}' must be a literal", global.name))
+            .with_help("compute the value inside a function and assign it with 'slay'"));
+    }
+
+    let expr_type = analyze_expression(stm_tab, &mut global.value)?;
+    if !is_assignable(&global.var_type, &expr_type) {
+        return Err(Diagnostic::error(global.value.span, format!("Type mismatch in global declaration '{}': expected {:?}, got {:?}", global.name, global.var_type, expr_type)));
     }
 
     Ok(())
 }
 
-fn analyze_function(stm_tab: &mut SymbolTable, func: &Function) -> Result<(), String> {
+// 42, -3.5, 'a', "ciao", fr, ghosted...
+fn is_literal(expr: &Expression) -> bool {
+    match &expr.kind {
+        ExpressionKind::Integer(_) | ExpressionKind::Long(_) | ExpressionKind::Float(_) | ExpressionKind::StringLit(_)
+        | ExpressionKind::CharLit(_) | ExpressionKind::Bool(_) | ExpressionKind::Null => true,
+        ExpressionKind::UnaryOp { op: UnaryOp::Neg, operand } => matches!(operand.kind,
+            ExpressionKind::Integer(_) | ExpressionKind::Long(_) | ExpressionKind::Float(_)),
+        _ => false
+    }
+}
+
+// Array e struct non si copiano né si stampano tutti interi
+fn is_aggregate(tipo: &Type) -> bool {
+    matches!(tipo, Type::Array(..) | Type::Struct(_))
+}
+
+fn analyze_function(stm_tab: &mut SymbolTable, func: &mut Function) {
+    if let Err(err) = check_type_exists(stm_tab, &func.return_type, func.span) {
+        stm_tab.report(err);
+    }
+
     stm_tab.enter_scope();
-    for param in &func.parameters {
-        stm_tab.declare(param.name.clone(), param.parameter_type.clone(), 0)?;
+    for param in &mut func.parameters {
+        if let Err(err) = check_type_exists(stm_tab, &param.parameter_type, param.span) {
+            stm_tab.report(err);
+        }
+        match stm_tab.declare(param.name.clone(), param.parameter_type.clone(), param.span) {
+            Ok(id) => param.decl = Some(id),
+            Err(err) => stm_tab.report(err)
+        }
     }
 
-    for stmt in &func.body {
-        analyze_statement(stm_tab, stmt, &func.return_type)?;
+    analyze_block(stm_tab, &mut func.body, &func.return_type);
+
+    stm_tab.exit_scope();
+}
+
+// Analizza una lista di statement
+// Un errore in uno statement viene registrato e si passa al successivo
+fn analyze_block(stm_tab: &mut SymbolTable, stmts: &mut [Statement], expected_return: &Type) {
+    for stmt in stmts {
+
+        if let Err(err) = analyze_statement(stm_tab, stmt, expected_return) {
+            stm_tab.report(err);
+        }
     }
+}
 
+// Corpo di ong/nah/mewing/sixSeven: le variabili dichiarate dentro non escono dal blocco
+// e possono nascondere quelle di fuori con lo stesso nome
+fn analyze_scoped_block(stm_tab: &mut SymbolTable, stmts: &mut [Statement], expected_return: &Type) {
+    stm_tab.enter_scope();
+    analyze_block(stm_tab, stmts, expected_return);
     stm_tab.exit_scope();
-    Ok(())
 }
 
-fn analyze_statement(stm_tab: &mut SymbolTable, stmt: &Statement, expected_return: &Type) -> Result<(), String>{
-    match stmt {
-        Statement::VarDecl {var_type, name, value} => {
+fn analyze_statement(stm_tab: &mut SymbolTable, stmt: &mut Statement, expected_return: &Type) -> Result<(), Diagnostic>{
+    match &mut stmt.kind {
+        StatementKind::VarDecl {var_type, name, value, decl} => {
             // Float non supportato diocane
-            if var_type == &Type::Chill {
-                return Err("Float type (chill) not supported in this version".to_string());
-            }
+            check_type_exists(stm_tab, var_type, stmt.span)?;
 
-            let expr_type = analyze_expression(stm_tab, value)?;
+            let expr_type = analyze_expression(stm_tab, value);
+
+            // la variabile la dichiaro comunque, così un valore sbagliato non si porta dietro
+            // una valanga di "not declared" negli statement dopo
+            *decl = Some(stm_tab.declare(name.clone(), var_type.clone(), stmt.span)?);
 
-            if &expr_type != var_type {
-                return Err(format!("Type mismatch in variable declaration '{}': expected {:?}, got {:?}", name, var_type, expr_type));
+            let expr_type = expr_type?;
+            if !is_assignable(var_type, &expr_type) {
+                return Err(Diagnostic::error(value.span, format!("Type mismatch in variable declaration '{}': expected {:?}, got {:?}", name, var_type, expr_type)));
             }
 
-            stm_tab.declare(name.clone(), var_type.clone(), 0)?;
             Ok(())
         },
-        Statement::Assignment {name, value} => {
+        StatementKind::ArrayDecl {elem_type, name, size, decl} => {
+            check_type_exists(stm_tab, elem_type, stmt.span)?;
+
+            *decl = Some(stm_tab.declare(name.clone(), Type::Array(Box::new(elem_type.clone()), Some(*size)), stmt.span)?);
+            Ok(())
+        },
+        StatementKind::StructVarDecl {struct_name, name, decl} => {
+            let var_type = Type::Struct(struct_name.clone());
+            check_type_exists(stm_tab, &var_type, stmt.span)?;
+
+            *decl = Some(stm_tab.declare(name.clone(), var_type, stmt.span)?);
+            Ok(())
+        },
+        StatementKind::Assignment {name, value, decl, var_type} => {
             let var_info = stm_tab.lookup(name)
-                .ok_or_else(|| format!("Variable '{}' not declared", name))?;
+                .ok_or_else(|| Diagnostic::error(stmt.span, format!("Variable '{}' not declared", name)))?;
+            *decl = Some(var_info.decl);
+            *var_type = Some(var_info.tipo.clone());
+
+            if var_info.constant {
+                return Err(Diagnostic::error(stmt.span, format!("Cannot assign to constant '{}'", name))
+                    .with_label(var_info.span, format!("'{}' declared as lockedIn here", name))
+                    .with_help("drop 'lockedIn' from the declaration if the value has to change"));
+            }
+
+            match var_info.tipo {
+                Type::Array(..) => return Err(Diagnostic::error(stmt.span, format!("Cannot assign to array '{}' as a whole", name))
+                    .with_help(format!("assign single elements with '{}[i] slay ...'", name))),
+                Type::Struct(_) => return Err(Diagnostic::error(stmt.span, format!("Cannot assign to struct '{}' as a whole", name))
+                    .with_help(format!("assign fields one at a time with '{}.field slay ...'", name))),
+                _ => {}
+            }
 
             // Analizza il tipo dell'espressione
             let expr_type = analyze_expression(stm_tab, value)?;
 
             // Controlla che i tipi siano compatibili
-            if expr_type != var_info.tipo {
-                return Err(format!(
-                    "Type mismatch in assignment to '{}': expected {:?}, got {:?}",
-                    name, var_info.tipo, expr_type
-                ));
+            if !is_assignable(&var_info.tipo, &expr_type) {
+                return Err(Diagnostic::error(value.span, format!("Type mismatch in assignment to '{}': expected {:?}, got {:?}", name, var_info.tipo, expr_type))
+                    .with_label(var_info.span, format!("'{}' declared here", name)));
             }
 
             Ok(())
         },
-        Statement::Print {expr} => {
-            analyze_expression(stm_tab, expr)?;
+        StatementKind::Store {target, value} => {
+            let target_type = analyze_expression(stm_tab, target)?;
+
+            // i letterali stanno in .data e sono condivisi: una vibes non si modifica
+            if let ExpressionKind::Index { array, .. } = &target.kind {
+                if array.resolved_type == Some(Type::Vibes) {
+                    return Err(Diagnostic::error(target.span, "Cannot assign to a character of a vibes")
+                        .with_help("build the string in a 'gyatt chad' buffer from alloc, then cast it with 'as vibes'"));
+                }
+            }
+
+            if is_aggregate(&target_type) {
+                return Err(Diagnostic::error(target.span, format!("Cannot assign a whole {:?}", target_type))
+                    .with_help("assign its elements or fields one at a time"));
+            }
+
+            let expr_type = analyze_expression(stm_tab, value)?;
+
+            if !is_assignable(&target_type, &expr_type) {
+                return Err(Diagnostic::error(value.span, format!("Type mismatch in assignment: expected {:?}, got {:?}", target_type, expr_type))
+                    .with_label(target.span, format!("this is {:?}", target_type)));
+            }
+
+            Ok(())
+        },
+        StatementKind::Print {expr} => {
+            let expr_type = analyze_expression(stm_tab, expr)?;
+
+            if expr_type == Type::Ghost {
+                return Err(Diagnostic::error(expr.span, "Cannot print the result of a ghost function"));
+            }
+
+            match expr_type {
+                Type::Array(..) => return Err(Diagnostic::error(expr.span, "Cannot print an array")
+                    .with_help("print the elements one at a time, e.g. in a sixSeven loop")),
+                Type::Struct(_) => return Err(Diagnostic::error(expr.span, "Cannot print a struct")
+                    .with_help("print its fields one at a time")),
+                Type::Pointer(_) => return Err(Diagnostic::error(expr.span, "Cannot print a pointer")
+                    .with_help("print the value it points to with '*'")),
+                _ => {}
+            }
+
             Ok(())
         }
-        Statement::Return {expr} => {
+        StatementKind::Call {name, args, signature} => {
+            // il valore di ritorno viene scartato, quindi va bene qualsiasi tipo
+            *signature = Some(analyze_call(stm_tab, name, args, stmt.span)?);
+            Ok(())
+        }
+        StatementKind::Return {expr} => {
             match expr {
                 Some(e) => {
                     let return_type = analyze_expression(stm_tab, e)?;
 
-                    if &return_type != expected_return {
-                        return Err(format!("Return mismatch: expected '{:?}', got {:?}", expected_return, return_type))
+                    if !is_assignable(expected_return, &return_type) {
+                        return Err(Diagnostic::error(e.span, format!("Return mismatch: expected '{:?}', got {:?}", expected_return, return_type)))
                     }
 
                     Ok(())
                 },
                 None => {
                     if expected_return != &Type::Ghost {
-                        return Err(format!("Function must return {:?}, but return statement has no return value", expected_return));
+                        return Err(Diagnostic::error(stmt.span, format!("Function must return {:?}, but return statement has no return value", expected_return)));
                     }
                     Ok(())
                 }
             }
         },
 
-        Statement::Break => {
-            // gestione break (per ora ok, controllare se siamo in loop è opzionale)
-            Ok(())
+        // ohio e fax hanno senso solo dentro un mewing o un sixSeven
+        StatementKind::Break | StatementKind::Continue if stm_tab.loop_depth == 0 => {
+            let keyword = if matches!(stmt.kind, StatementKind::Break) { "ohio" } else { "fax" };
+            Err(Diagnostic::error(stmt.span, format!("'{}' outside of a loop", keyword))
+                .with_help("it can only be used inside the body of a mewing or sixSeven loop"))
         },
+        StatementKind::Break | StatementKind::Continue => Ok(()),
 
         // Costrutti implementati da me cazzo
-        Statement::If { condition, then_body, else_body } => {
-            // Analizza condizione
-            analyze_expression(stm_tab, condition)?;
+        StatementKind::If { condition, then_body, else_body } => {
+            // Analizza condizione (se è sbagliata controllo comunque i body)
+            if let Err(err) = analyze_condition(stm_tab, condition, "ong") {
+                stm_tab.report(err);
+            }
 
             // Analizza then body
-            for stmt in then_body {
-                analyze_statement(stm_tab, stmt, expected_return)?;
-            }
+            analyze_scoped_block(stm_tab, then_body, expected_return);
 
             // Analizza else body se presente
             if let Some(else_stmts) = else_body {
-                for stmt in else_stmts {
-                    analyze_statement(stm_tab, stmt, expected_return)?;
-                }
+                analyze_scoped_block(stm_tab, else_stmts, expected_return);
             }
 
             Ok(())
         },
 
-        Statement::While { condition, body } => {
+        StatementKind::While { condition, body } => {
             // Analizza condizione
-            analyze_expression(stm_tab, condition)?;
+            if let Err(err) = analyze_condition(stm_tab, condition, "mewing") {
+                stm_tab.report(err);
+            }
 
             // Analizza body
-            for stmt in body {
-                analyze_statement(stm_tab, stmt, expected_return)?;
-            }
+            stm_tab.loop_depth += 1;
+            analyze_scoped_block(stm_tab, body, expected_return);
+            stm_tab.loop_depth -= 1;
 
             Ok(())
         },
 
-        Statement::For { init, condition, increment, body } => {
+        StatementKind::For { init, condition, increment, body } => {
+            // la variabile dell'init è visibile solo dentro al for, così due sixSeven di fila
+            // possono dichiarare entrambi la loro i
+            stm_tab.enter_scope();
+
             // Analizza init
-            analyze_statement(stm_tab, init, expected_return)?;
+            if let Err(err) = analyze_statement(stm_tab, init, expected_return) {
+                stm_tab.report(err);
+            }
 
             // Analizza condition
-            analyze_expression(stm_tab, condition)?;
+            if let Err(err) = analyze_condition(stm_tab, condition, "sixSeven") {
+                stm_tab.report(err);
+            }
 
             // Analizza body
-            for stmt in body {
-                analyze_statement(stm_tab, stmt, expected_return)?;
-            }
+            stm_tab.loop_depth += 1;
+            analyze_scoped_block(stm_tab, body, expected_return);
+            stm_tab.loop_depth -= 1;
 
             // Analizza increment
-            analyze_statement(stm_tab, increment, expected_return)?;
-
-            Ok(())
+            let result = analyze_statement(stm_tab, increment, expected_return);
+            stm_tab.exit_scope();
+            result
         }
     }
 
 }
 
-fn analyze_expression(stm_tab: &SymbolTable, expr: &Expression) -> Result<Type, String> {
-    match expr {
-        Expression::Integer(_) => Ok(Type::Based),
-        Expression::Long(_) => Ok(Type::SuperBased),
-        Expression::StringLit(_) => Ok(Type::Vibes),
-        Expression::CharLit(_) => Ok(Type::Chad),
+// La condizione di ong/mewing/sixSeven deve essere un nocap, niente interi usati come bool
+fn analyze_condition(stm_tab: &SymbolTable, condition: &mut Expression, construct: &str) -> Result<(), Diagnostic> {
+    let condition_type = analyze_expression(stm_tab, condition)?;
 
-        Expression::Variable(name) => {
-            let var_info = stm_tab.lookup(name).ok_or_else(|| format!("Variable '{}' not declared", name))?;
+    if condition_type != Type::NoCap {
+        return Err(Diagnostic::error(condition.span, format!("Condition of '{}' must be NoCap, got {:?}", construct, condition_type))
+            .with_help("use a comparison, e.g. 'x != 0'"));
+    }
+
+    Ok(())
+}
+
+// Calcola il tipo dell'espressione e se lo segna nell'AST, così il codegen non deve ricalcolarlo
+fn analyze_expression(stm_tab: &SymbolTable, expr: &mut Expression) -> Result<Type, Diagnostic> {
+    let tipo = resolve_expression(stm_tab, expr)?;
+    expr.resolved_type = Some(tipo.clone());
+    Ok(tipo)
+}
+
+fn resolve_expression(stm_tab: &SymbolTable, expr: &mut Expression) -> Result<Type, Diagnostic> {
+    match &mut expr.kind {
+        ExpressionKind::Integer(_) => Ok(Type::Based),
+        ExpressionKind::Long(_) => Ok(Type::SuperBased),
+        ExpressionKind::Float(_) => Ok(Type::Chill),
+        ExpressionKind::StringLit(_) => Ok(Type::Vibes),
+        ExpressionKind::CharLit(_) => Ok(Type::Chad),
+        ExpressionKind::Bool(_) => Ok(Type::NoCap),
+        ExpressionKind::Null => Ok(Type::Pointer(Box::new(Type::Ghost))),
+
+        ExpressionKind::Variable{name, decl} => {
+            let var_info = stm_tab.lookup(name).ok_or_else(|| Diagnostic::error(expr.span, format!("Variable '{}' not declared", name)))?;
 
+            *decl = Some(var_info.decl);
             Ok(var_info.tipo.clone())
         },
 
-        Expression::BinOp{left, op, right} => {
+        ExpressionKind::Index{array, index} => analyze_index(stm_tab, array, index),
+
+        ExpressionKind::Field{base, field} => analyze_field(stm_tab, base, field, expr.span),
+
+        ExpressionKind::Call{name, args, signature} => {
+            let resolved = analyze_call(stm_tab, name, args, expr.span)?;
+            let return_type = resolved.return_type.clone();
+            *signature = Some(resolved);
+            Ok(return_type)
+        },
+
+        ExpressionKind::BinOp{left, op, right} => {
             let left_type = analyze_expression(stm_tab, left)?;
             let right_type = analyze_expression(stm_tab, right)?;
-            check_binary_op(&left_type, op, &right_type)
+            check_binary_op(&left_type, op, &right_type, expr.span)
+        },
+
+        ExpressionKind::UnaryOp{op: UnaryOp::AddressOf, operand} => {
+            // si può prendere l'indirizzo solo di qualcosa che sta in memoria
+            if !is_lvalue(operand) {
+                return Err(Diagnostic::error(expr.span, "Cannot take the address of a temporary value")
+                    .with_help("'&' works on variables, elements, fields and '*pointer'"));
+            }
+
+            // le costanti stanno in .rodata: un puntatore permetterebbe di scriverci
+            if let ExpressionKind::Variable { name, .. } = &operand.kind {
+                if let Some(info) = stm_tab.lookup(name).filter(|info| info.constant) {
+                    return Err(Diagnostic::error(expr.span, format!("Cannot take the address of constant '{}'", name))
+                        .with_label(info.span, format!("'{}' declared as lockedIn here", name))
+                        .with_help("copy it into a local variable and take the address of that"));
+                }
+            }
+
+            let operand_type = analyze_expression(stm_tab, operand)?;
+            Ok(Type::Pointer(Box::new(operand_type)))
+        },
+
+        ExpressionKind::UnaryOp{op, operand} => {
+            let operand_type = analyze_expression(stm_tab, operand)?;
+            check_unary_op(op, &operand_type, expr.span)
+        },
+
+        ExpressionKind::Cast{expr: inner, target} => {
+            let from = analyze_expression(stm_tab, inner)?;
+            check_cast(&from, target, expr.span)
+        }
+    }
+}
+
+// Espressioni che hanno un indirizzo: si possono assegnare e passare a '&'
+fn is_lvalue(expr: &Expression) -> bool {
+    matches!(expr.kind,
+        ExpressionKind::Variable{..} | ExpressionKind::Index{..} | ExpressionKind::Field{..}
+        | ExpressionKind::UnaryOp{op: UnaryOp::Deref, ..})
+}
+
+// Controlla che si stia indicizzando un array (o un puntatore, p[i] == *(p + i), o una vibes,
+// che legge un chad) e che l'indice sia un based; ritorna il tipo dell'elemento
+fn analyze_index(stm_tab: &SymbolTable, array: &mut Expression, index: &mut Expression) -> Result<Type, Diagnostic> {
+    let array_type = analyze_expression(stm_tab, array)?;
+
+    let chad = Box::new(Type::Chad);
+    let (elem_type, size) = match &array_type {
+        Type::Array(elem_type, size) => (elem_type, *size),
+        Type::Pointer(elem_type) if **elem_type != Type::Ghost => (elem_type, None),
+        Type::Vibes => (&chad, None),
+        _ => return Err(Diagnostic::error(array.span, format!("Cannot index a value of type {:?}: not an array", array_type)))
+    };
+
+    let index_type = analyze_expression(stm_tab, index)?;
+    if index_type != Type::Based {
+        return Err(Diagnostic::error(index.span, format!("Array index must be Based, got {:?}", index_type)));
+    }
+
+    // se indice e dimensione sono noti già adesso, l'accesso fuori dai limiti lo becchiamo subito
+    if let (ExpressionKind::Integer(i), Some(size)) = (&index.kind, size) {
+        if *i < 0 || *i as usize >= size {
+            return Err(Diagnostic::error(index.span, format!("Index {} out of bounds for array of size {}", i, size)));
+        }
+    }
+
+    Ok((**elem_type).clone())
+}
+
+// Accesso a un campo: la base deve essere uno struct che ha quel campo
+fn analyze_field(stm_tab: &SymbolTable, base: &mut Expression, field: &str, span: Span) -> Result<Type, Diagnostic> {
+    let base_type = analyze_expression(stm_tab, base)?;
+
+    // p.x con p puntatore a struct legge il campo dello struct puntato, senza scrivere (*p).x
+    let base_type = match base_type {
+        Type::Pointer(inner) if matches!(*inner, Type::Struct(_)) => *inner,
+        other => other
+    };
+
+    let Type::Struct(struct_name) = &base_type else {
+        return Err(Diagnostic::error(span, format!("Cannot access field '{}' of {:?}: not a struct", field, base_type)));
+    };
+
+    let info = stm_tab.lookup_struct(struct_name)
+        .ok_or_else(|| Diagnostic::error(span, format!("Unknown type '{}'", struct_name)))?;
+
+    match info.fields.iter().find(|(name, _)| name == field) {
+        Some((_, field_type)) => Ok(field_type.clone()),
+        None => {
+            let available: Vec<&str> = info.fields.iter().map(|(name, _)| name.as_str()).collect();
+            Err(Diagnostic::error(span, format!("Struct '{}' has no field '{}'", struct_name, field))
+                .with_label(info.span, format!("'{}' declared here", struct_name))
+                .with_note(format!("available fields: {}", available.join(", "))))
+        }
+    }
+}
+
+// Un valore va bene se ha lo stesso tipo della destinazione; un array di qualsiasi
+// dimensione si può passare a un parametro 'based arr[]', 'ghosted' (e il puntatore generico
+// di alloc) va in qualsiasi puntatore, un puntatore generico come il parametro di free
+// accetta qualsiasi puntatore e un based si allarga da solo a superBased o chill
+// (il contrario perderebbe bit)
+fn is_assignable(param_type: &Type, arg_type: &Type) -> bool {
+    match (param_type, arg_type) {
+        (Type::SuperBased | Type::Chill, Type::Based) => true,
+        (Type::Array(param_elem, None), Type::Array(arg_elem, _)) => param_elem == arg_elem,
+        (Type::Pointer(_), Type::Pointer(arg_inner)) if **arg_inner == Type::Ghost => true,
+        (Type::Pointer(param_inner), Type::Pointer(_)) if **param_inner == Type::Ghost => true,
+        _ => param_type == arg_type
+    }
+}
+
+// Controlla che la funzione esista, che il numero di argomenti sia giusto e che i tipi coincidano.
+// Ritorna la firma della funzione, che il codegen usa per passare gli argomenti
+fn analyze_call(stm_tab: &SymbolTable, name: &str, args: &mut [Expression], span: Span) -> Result<Signature, Diagnostic> {
+    if let Some(builtin) = builtin(name) {
+        return analyze_builtin_call(stm_tab, name, &builtin, args, span);
+    }
+
+    let func_info = stm_tab.lookup_function(name)
+        .ok_or_else(|| Diagnostic::error(span, format!("Function '{}' not declared", name)))?;
+
+    if args.len() != func_info.parameters.len() {
+        return Err(Diagnostic::error(span, format!("Function '{}' expects {} arguments, but {} were given", name, func_info.parameters.len(), args.len()))
+            .with_label(func_info.span, "function declared here"));
+    }
+
+    for (i, (arg, param_type)) in args.iter_mut().zip(&func_info.parameters).enumerate() {
+        let arg_type = analyze_expression(stm_tab, arg)?;
+
+        if !is_assignable(param_type, &arg_type) {
+            return Err(Diagnostic::error(arg.span, format!("Type mismatch in argument {} of call to '{}': expected {:?}, got {:?}", i + 1, name, param_type, arg_type))
+                .with_label(func_info.span, "function declared here"));
         }
     }
+
+    Ok(Signature { parameters: func_info.parameters.clone(), return_type: func_info.return_type.clone() })
 }
 
-fn check_binary_op(left_type: &Type, op: &BinOp, right_type: &Type) -> Result<Type, String> {
-    use BinOp::*;
+// Come analyze_call, ma una builtin non ha una dichiarazione da indicare: spiego come si usa
+fn analyze_builtin_call(stm_tab: &SymbolTable, name: &str, builtin: &Builtin, args: &mut [Expression], span: Span) -> Result<Signature, Diagnostic> {
+    if args.len() != builtin.parameters.len() {
+        return Err(Diagnostic::error(span, format!("Builtin '{}' expects {} arguments, but {} were given", name, builtin.parameters.len(), args.len()))
+            .with_note(builtin.usage));
+    }
+
+    for (i, (arg, param_type)) in args.iter_mut().zip(&builtin.parameters).enumerate() {
+        let arg_type = analyze_expression(stm_tab, arg)?;
+
+        if !is_assignable(param_type, &arg_type) {
+            return Err(Diagnostic::error(arg.span, format!("Type mismatch in argument {} of call to '{}': expected {:?}, got {:?}", i + 1, name, param_type, arg_type))
+                .with_note(builtin.usage));
+        }
+    }
+
+    // readVibes scrive fino a size byte: se array e size sono noti già adesso, controllo che ci stiano
+    if let ("readVibes", [buffer, size]) = (name, &*args) {
+        if let (Some(Type::Array(_, Some(len))), ExpressionKind::Integer(n)) = (&buffer.resolved_type, &size.kind) {
+            if *n as i64 > *len as i64 {
+                return Err(Diagnostic::error(size.span, format!("Size {} is larger than the buffer of {} chad", n, len))
+                    .with_label(buffer.span, "buffer passed here"));
+            }
+        }
+    }
+
+    Ok(Signature { parameters: builtin.parameters.clone(), return_type: builtin.return_type.clone() })
+}
 
+fn check_binary_op(left_type: &Type, op: &BinOp, right_type: &Type, span: Span) -> Result<Type, Diagnostic> {
     match op {
+        // aritmetica dei puntatori: p + n e p - n si spostano di n elementi, p - q conta gli elementi tra i due
+        BinOp::Add | BinOp::Sub if matches!(left_type, Type::Pointer(_)) || matches!(right_type, Type::Pointer(_)) => {
+            match (left_type, op, right_type) {
+                (Type::Pointer(inner), _, Type::Based) if **inner != Type::Ghost => Ok(left_type.clone()),
+                (Type::Based, BinOp::Add, Type::Pointer(inner)) if **inner != Type::Ghost => Ok(right_type.clone()),
+                (Type::Pointer(inner), BinOp::Sub, Type::Pointer(_)) if left_type == right_type && **inner != Type::Ghost => Ok(Type::Based),
+                _ => Err(Diagnostic::error(span, format!("Invalid pointer arithmetic {:?}, between '{:?}' and '{:?}'", op, left_type, right_type))
+                    .with_note("a pointer can be moved by a based offset, or subtracted from a pointer of the same type"))
+            }
+        },
+
+        // concatenazione: il risultato è una stringa nuova sullo heap
+        BinOp::Add if *left_type == Type::Vibes || *right_type == Type::Vibes => {
+            if left_type != right_type {
+                return Err(Diagnostic::error(span, format!("Cannot concatenate '{:?}' and '{:?}': both must be Vibes", left_type, right_type))
+                    .with_help("convert a number first, e.g. 'x as vibes'"));
+            }
+            Ok(Type::Vibes)
+        },
+
         BinOp::Add | BinOp::Sub | BinOp::Mul | BinOp::Div => {
-            // Solo interi supportati (float rimosso porca puttana)
-            let is_left_numeric = matches!(left_type, Type::Based | Type::SuperBased);
-            let is_right_numeric = matches!(right_type, Type::Based | Type::SuperBased);
+            check_numeric_operands(left_type, op, right_type, span, "operation")
+        },
 
-            if !is_left_numeric || !is_right_numeric {
-                return Err(format!("Invalid operation {:?}, between '{:?}' and '{:?}': both must be numeric types!", op, left_type, right_type));
+        BinOp::Mod | BinOp::BitAnd | BinOp::BitOr | BinOp::BitXor | BinOp::Shl | BinOp::Shr => {
+            // resto e operazioni sui bit hanno senso solo sugli interi
+            let is_left_integer = matches!(left_type, Type::Based | Type::SuperBased);
+            let is_right_integer = matches!(right_type, Type::Based | Type::SuperBased);
+
+            if !is_left_integer || !is_right_integer {
+                return Err(Diagnostic::error(span, format!("Invalid operation {:?}, between '{:?}' and '{:?}': both must be integer types!", op, left_type, right_type)));
             }
 
             // Promozione tipo: SuperBased > Based
@@ -235,23 +793,125 @@ fn check_binary_op(left_type: &Type, op: &BinOp, right_type: &Type) -> Result<Ty
         },
 
         BinOp::Equal | BinOp::NotEqual => {
-            if left_type != right_type {
-                return Err(format!("Cannot compare '{:?}' and '{:?}': types must match", left_type, right_type));
+            if is_aggregate(left_type) || is_aggregate(right_type) {
+                return Err(Diagnostic::error(span, format!("Cannot compare '{:?}' and '{:?}'", left_type, right_type))
+                    .with_help("compare elements or fields one at a time"));
             }
 
-            Ok(Type::Based) // 1 - true | 0 - false: per ora voglio usare un intero come bool
+            // p == ghosted va bene con qualsiasi puntatore
+            if !is_assignable(left_type, right_type) && !is_assignable(right_type, left_type) {
+                return Err(Diagnostic::error(span, format!("Cannot compare '{:?}' and '{:?}': types must match", left_type, right_type)));
+            }
+
+            Ok(Type::NoCap)
+        },
+
+        // le vibes si confrontano carattere per carattere, in ordine alfabetico (dei byte)
+        BinOp::Less | BinOp::Greater | BinOp::LessEq | BinOp::GreaterEq if *left_type == Type::Vibes && *right_type == Type::Vibes => {
+            Ok(Type::NoCap)
         },
 
         BinOp::Less | BinOp::Greater | BinOp::LessEq | BinOp::GreaterEq => {
-            // Solo interi per confronti numerici
-            let is_left_numeric = matches!(left_type, Type::Based | Type::SuperBased);
-            let is_right_numeric = matches!(right_type, Type::Based | Type::SuperBased);
+            check_numeric_operands(left_type, op, right_type, span, "comparison")?;
+            Ok(Type::NoCap)
+        },
 
-            if !is_left_numeric || !is_right_numeric {
-                return Err(format!("Invalid comparison {:?}, between '{:?}' and '{:?}': both must be numeric types!", op, left_type, right_type));
+        BinOp::And | BinOp::Or => {
+            if left_type != &Type::NoCap || right_type != &Type::NoCap {
+                return Err(Diagnostic::error(span, format!("Invalid logical operation {:?}, between '{:?}' and '{:?}': both must be NoCap!", op, left_type, right_type)));
             }
 
-            Ok(Type::Based) // 1 - true | 0 - false: per ora voglio usare un intero come bool
+            Ok(Type::NoCap)
         }
     }
-}
\ No newline at end of file
+}
+
+// Operandi di + - * / e dei confronti: interi o chill. Un based si converte da solo
+// in chill, un superBased no (in RV32 non c'è un'istruzione per farlo)
+fn check_numeric_operands(left_type: &Type, op: &BinOp, right_type: &Type, span: Span, what: &str) -> Result<Type, Diagnostic> {
+    let is_left_numeric = matches!(left_type, Type::Based | Type::SuperBased | Type::Chill);
+    let is_right_numeric = matches!(right_type, Type::Based | Type::SuperBased | Type::Chill);
+
+    if !is_left_numeric || !is_right_numeric {
+        return Err(Diagnostic::error(span, format!("Invalid {} {:?}, between '{:?}' and '{:?}': both must be numeric types!", what, op, left_type, right_type)));
+    }
+
+    match (left_type, right_type) {
+        (Type::Chill, Type::SuperBased) | (Type::SuperBased, Type::Chill) => {
+            Err(Diagnostic::error(span, format!("Invalid {} {:?}, between '{:?}' and '{:?}': superBased and chill cannot be mixed", what, op, left_type, right_type)))
+        },
+        (Type::Chill, _) | (_, Type::Chill) => Ok(Type::Chill),
+        // Promozione tipo: SuperBased > Based
+        (Type::SuperBased, _) | (_, Type::SuperBased) => Ok(Type::SuperBased),
+        _ => Ok(Type::Based)
+    }
+}
+
+fn check_unary_op(op: &UnaryOp, operand_type: &Type, span: Span) -> Result<Type, Diagnostic> {
+    match op {
+        UnaryOp::Neg => {
+            if !matches!(operand_type, Type::Based | Type::SuperBased | Type::Chill) {
+                return Err(Diagnostic::error(span, format!("Invalid operation {:?} on '{:?}': operand must be a numeric type!", op, operand_type)));
+            }
+
+            Ok(operand_type.clone())
+        },
+        UnaryOp::BitNot => {
+            if !matches!(operand_type, Type::Based | Type::SuperBased) {
+                return Err(Diagnostic::error(span, format!("Invalid operation {:?} on '{:?}': operand must be an integer type!", op, operand_type)));
+            }
+
+            Ok(operand_type.clone())
+        },
+        UnaryOp::Not => {
+            if operand_type != &Type::NoCap {
+                return Err(Diagnostic::error(span, format!("Cannot apply '!' to '{:?}': operand must be NoCap", operand_type)));
+            }
+
+            Ok(Type::NoCap)
+        },
+        UnaryOp::Deref => {
+            match operand_type {
+                Type::Pointer(inner) if **inner != Type::Ghost => Ok((**inner).clone()),
+                Type::Pointer(_) => Err(Diagnostic::error(span, "Cannot dereference 'ghosted'")),
+                _ => Err(Diagnostic::error(span, format!("Cannot dereference '{:?}': not a pointer", operand_type))
+                    .with_help("declare it as a pointer, e.g. 'gyatt based p slay &x;'"))
+            }
+        },
+        // gestito in analyze_expression, che deve guardare l'operando e non solo il suo tipo
+        UnaryOp::AddressOf => unreachable!()
+    }
+}
+
+// Conversioni permesse con 'as': chad <-> based, based <-> superBased, based <-> chill.
+// superBased <-> chill si fa passando da based (in RV32 non c'è un'istruzione diretta).
+// gyatt chad <-> vibes serve per le stringhe costruite sullo heap: sono entrambi indirizzi di chad.
+// based <-> vibes converte tra il numero e il suo testo (42 <-> "42")
+fn check_cast(from: &Type, to: &Type, span: Span) -> Result<Type, Diagnostic> {
+    let is_chad_pointer = |tipo: &Type| matches!(tipo, Type::Pointer(inner) if **inner == Type::Chad);
+    let allowed = (from == to && !is_aggregate(from)) || matches!((from, to),
+        (Type::Chad, Type::Based) | (Type::Based, Type::Chad)
+        | (Type::Based, Type::SuperBased) | (Type::SuperBased, Type::Based)
+        | (Type::Based, Type::Chill) | (Type::Chill, Type::Based)
+        | (Type::Based, Type::Vibes) | (Type::Vibes, Type::Based))
+        || (is_chad_pointer(from) && *to == Type::Vibes)
+        || (*from == Type::Vibes && is_chad_pointer(to));
+
+    if !allowed {
+        let error = Diagnostic::error(span, format!("Cannot cast '{:?}' to '{:?}'", from, to));
+        return Err(match (from, to) {
+            // entrambi i tipi si convertono in based: basta fare due cast
+            (Type::SuperBased | Type::Chill | Type::Chad, Type::SuperBased | Type::Chill | Type::Chad) => {
+                let keyword = match to {
+                    Type::Chill => "chill",
+                    Type::Chad => "chad",
+                    _ => "superBased"
+                };
+                error.with_help(format!("cast through based first, e.g. '(x as based) as {}'", keyword))
+            },
+            _ => error.with_note("allowed casts: chad <-> based, based <-> superBased, based <-> chill, based <-> vibes, gyatt chad <-> vibes")
+        });
+    }
+
+    Ok(to.clone())
+}
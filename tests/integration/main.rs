@@ -0,0 +1,882 @@
+// Test di integrazione: compiliamo i programmi in tests/fixtures con il binario sigma
+// e controlliamo l'assembly generato (qui non c'è un RISC-V per eseguirlo); quelli in
+// tests/fixtures/errors devono fallire con i diagnostici giusti
+
+use std::path::PathBuf;
+use std::process::Command;
+
+// Compila tests/fixtures/<name> e ritorna l'assembly generato
+fn compile(name: &str) -> String {
+    let fixture = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests").join("fixtures").join(name);
+    let output = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(name).with_extension("s");
+
+    let result = Command::new(env!("CARGO_BIN_EXE_sigma"))
+        .arg(&fixture)
+        .arg(&output)
+        .output()
+        .expect("failed to run sigma");
+    assert!(result.status.success(), "compilation of {} failed:\n{}", name, String::from_utf8_lossy(&result.stderr));
+
+    std::fs::read_to_string(&output).expect("missing generated assembly")
+}
+
+// Compila tests/fixtures/errors/<name>, che deve fallire, e ritorna i diagnostici (su stderr)
+fn compile_error(name: &str) -> String {
+    let fixture = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests").join("fixtures").join("errors").join(name);
+    let output = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(name).with_extension("err.s");
+
+    let result = Command::new(env!("CARGO_BIN_EXE_sigma"))
+        .arg(&fixture)
+        .arg(&output)
+        .output()
+        .expect("failed to run sigma");
+    assert!(!result.status.success(), "compilation of {} should have failed", name);
+
+    String::from_utf8_lossy(&result.stderr).into_owned()
+}
+
+// Ogni messaggio deve comparire come "error: <messaggio>" e il totale deve tornare
+fn assert_errors(diagnostics: &str, expected: &[&str]) {
+    for message in expected {
+        assert!(diagnostics.contains(&format!("error: {}\n", message)), "missing error '{}' in:\n{}", message, diagnostics);
+    }
+    let count = diagnostics.lines().filter(|line| line.starts_with("error: ")).count();
+    assert_eq!(count, expected.len(), "unexpected errors in:\n{}", diagnostics);
+}
+
+// Le righe della funzione bussin <name>
+fn function<'a>(asm: &'a str, name: &str) -> Vec<&'a str> {
+    functions(asm).into_iter()
+        .find(|(function, _)| function == name)
+        .map(|(_, lines)| lines)
+        .unwrap_or_else(|| panic!("no function {} in the generated assembly", name))
+}
+
+// Le funzioni bussin dell'assembly, come (nome, righe): ognuna inizia con "# Funzione <nome>"
+fn functions(asm: &str) -> Vec<(String, Vec<&str>)> {
+    let mut functions: Vec<(String, Vec<&str>)> = Vec::new();
+    for line in asm.lines() {
+        if let Some(name) = line.strip_prefix("# Funzione ") {
+            functions.push((name.to_string(), Vec::new()));
+        } else if line.trim() == ".data" {
+            break;
+        } else if let Some((_, lines)) = functions.last_mut() {
+            lines.push(line.trim());
+        }
+    }
+    functions
+}
+
+// Le righe degli helper del runtime: stanno in .text prima della prima funzione bussin
+fn helper_lines(asm: &str) -> Vec<&str> {
+    asm.lines()
+        .skip_while(|line| line.trim() != ".text")
+        .take_while(|line| !line.starts_with("# Funzione "))
+        .map(str::trim)
+        .collect()
+}
+
+// Dimensione del frame, presa dal prologo "addi sp, sp, -N"
+// (o "li t6, -N" + "add sp, sp, t6" quando N non sta in 12 bit)
+fn frame_size(name: &str, lines: &[&str]) -> i32 {
+    lines.iter()
+        .zip(lines.iter().skip(1))
+        .find_map(|(line, next)| {
+            line.strip_prefix("addi sp, sp, -")
+                .or_else(|| line.strip_prefix("li   t6, -").filter(|_| *next == "add  sp, sp, t6"))
+        })
+        .and_then(|size| size.parse().ok())
+        .unwrap_or_else(|| panic!("no prologue in function {}", name))
+}
+
+// Le istruzioni che spostano sp: "addi sp, sp, N" o "add sp, sp, t6" con i frame grandi
+fn moves_sp(line: &str) -> bool {
+    line.starts_with("addi sp, sp,") || line.starts_with("add  sp, sp, t6")
+}
+
+// Accesso a memoria relativo a un registro base, es. "(s0)": (offset, byte letti o scritti)
+fn base_access(line: &str, base: &str) -> Option<(i32, i32)> {
+    let code = line.split('#').next()?;
+    let end = code.find(base)?;
+    let start = code[..end].rfind([',', ' '])? + 1;
+    let offset = code[start..end].trim().parse().ok()?;
+    let width = match code.split_whitespace().next()? {
+        "sb" | "lb" | "lbu" => 1,
+        _ => 4
+    };
+    Some((offset, width))
+}
+
+// Ogni variabile e ogni temporaneo deve stare tra s0 e i registri salvati (ra e s0 del chiamante)
+fn assert_frames_are_sound(asm: &str) {
+    let functions = functions(asm);
+    assert!(!functions.is_empty(), "no functions in the generated assembly");
+
+    for (name, lines) in &functions {
+        let size = frame_size(name, lines);
+        assert_eq!(size % 16, 0, "frame of {} is {} bytes, not 16-byte aligned", name, size);
+
+        // con offset oltre i 12 bit l'indirizzo passa da t6: "li t6, N" + "add t6, s0, t6" e poi 0(t6)
+        let mut t6_immediate = None;
+        let mut t6_offset = None;
+        for line in lines {
+            if line.starts_with("sw ra,") || line.starts_with("sw s0,") || line.starts_with("lw ra,") || line.starts_with("lw s0,") {
+                continue;
+            }
+            if let Some(access) = base_access(line, "(t6)") {
+                let offset = t6_offset.unwrap_or_else(|| panic!("'{}' in {} uses t6 without a frame offset in it", line, name));
+                let (relative, width) = access;
+                assert!(offset + relative >= 0 && offset + relative + width <= size - 8,
+                    "'{}' in {} is outside the {}-byte frame (t6 = s0 + {})", line, name, size, offset);
+            }
+            let immediate = line.strip_prefix("li   t6, ").and_then(|value| value.split_whitespace().next()?.parse::<i32>().ok());
+            if *line == "add  t6, s0, t6" {
+                t6_offset = t6_immediate;
+            } else if line.contains("t6") && !line.contains("(t6)") {
+                t6_offset = None;
+            }
+            t6_immediate = immediate;
+
+            if let Some((offset, width)) = base_access(line, "(s0)") {
+                assert!(offset >= 0 && offset + width <= size - 8,
+                    "'{}' in {} is outside the {}-byte frame (saved registers start at {})", line, name, size, size - 8);
+            }
+            if let Some(offset) = line.strip_prefix("addi a0, s0, ") {
+                let offset: i32 = offset.split_whitespace().next().unwrap().parse().unwrap();
+                assert!(offset >= 0 && offset < size - 8, "'{}' in {} points outside the frame", line, name);
+            }
+        }
+    }
+}
+
+#[test]
+fn nested_declarations_stay_inside_the_frame() {
+    let asm = compile("nested_decls.sgm");
+    assert_frames_are_sound(&asm);
+}
+
+#[test]
+fn sp_only_moves_in_prologue_and_epilogue() {
+    let asm = compile("nested_decls.sgm");
+
+    for (name, lines) in functions(&asm) {
+        let moves: Vec<&&str> = lines.iter().filter(|line| moves_sp(line)).collect();
+        assert_eq!(moves.len(), 2, "sp moves outside prologue/epilogue in {}: {:?}", name, moves);
+    }
+
+    // anche gli helper, che chiamano altri helper o fanno ecall, tengono sp allineato a 16
+    let helpers = helper_lines(&asm);
+    assert!(helpers.contains(&"print_int:"), "no runtime helpers in the generated assembly");
+    for line in helpers {
+        if let Some(amount) = line.strip_prefix("addi sp, sp, ") {
+            let amount: i32 = amount.split_whitespace().next().unwrap().parse().unwrap();
+            assert_eq!(amount % 16, 0, "'{}' in the runtime helpers breaks the 16-byte alignment of sp", line);
+        }
+    }
+}
+
+#[test]
+fn sibling_scopes_reuse_slots() {
+    let asm = compile("sibling_scopes.sgm");
+    assert_frames_are_sound(&asm);
+
+    // due array da 400 byte in blocchi separati: senza riuso servirebbero almeno 800 byte
+    let functions = functions(&asm);
+    let (name, lines) = functions.iter().find(|(name, _)| name == "sigma").unwrap();
+    let size = frame_size(name, lines);
+    assert!(size < 800, "sigma frame is {} bytes, sibling blocks should share their slots", size);
+}
+
+// Sezione in cui l'assembly definisce una label
+fn section_of(asm: &str, label: &str) -> Option<String> {
+    let mut section = ".text".to_string();
+    for line in asm.lines().map(str::trim) {
+        if line == ".text" || line == ".data" || line == ".bss" {
+            section = line.to_string();
+        } else if let Some(name) = line.strip_prefix(".section ") {
+            section = name.to_string();
+        } else if line.starts_with(&format!("{}:", label)) {
+            return Some(section);
+        }
+    }
+    None
+}
+
+#[test]
+fn globals_go_in_the_right_section() {
+    let asm = compile("globals.sgm");
+    assert_frames_are_sound(&asm);
+
+    assert_eq!(section_of(&asm, ".Lglobal_start").as_deref(), Some(".data"));
+    assert_eq!(section_of(&asm, ".Lglobal_counter").as_deref(), Some(".bss"));
+    assert_eq!(section_of(&asm, ".Lglobal_LIMIT").as_deref(), Some(".rodata"));
+    assert_eq!(section_of(&asm, ".Lglobal_RATE").as_deref(), Some(".rodata"));
+
+    // tick scrive la globale passando dalla sua label
+    let functions = functions(&asm);
+    let (_, tick) = functions.iter().find(|(name, _)| name == "tick").unwrap();
+    assert!(tick.iter().any(|line| line.starts_with("la t0, .Lglobal_counter")), "tick does not store into counter");
+}
+
+// Contenuto (tra le virgolette) delle stringhe .asciz dell'assembly
+fn asciz_strings(asm: &str) -> Vec<&str> {
+    asm.lines()
+        .filter_map(|line| line.split_once(".asciz \""))
+        .map(|(_, rest)| rest.strip_suffix('"').expect("asciz string not closed on its line"))
+        .collect()
+}
+
+#[test]
+fn string_escapes_are_rewritten_for_the_assembler() {
+    let asm = compile("escapes.sgm");
+    let strings = asciz_strings(&asm);
+
+    for expected in ["back\\\\slash", "line\\012break\\011tab", "A\\303\\250", "\\377\\200", "// dentro una stringa", "say \\\"hi\\\""] {
+        assert!(strings.contains(&expected), "missing .asciz \"{}\" in {:?}", expected, strings);
+    }
+
+    // '\xe8' è il byte 0xE8 come 'è'
+    assert!(asm.contains("li a0, 232"), "'\\xe8' not loaded as 232");
+
+    // nessun byte di controllo finisce crudo nell'assembly
+    assert!(strings.iter().all(|s| s.bytes().all(|b| (b' '..=b'~').contains(&b))), "raw bytes in {:?}", strings);
+}
+
+// Le righe di un helper, dalla sua etichetta fino a quella del prossimo helper
+fn helper<'a>(asm: &'a str, name: &str) -> Vec<&'a str> {
+    let label = format!("{}:", name);
+    helper_lines(asm)
+        .into_iter()
+        .skip_while(|line| *line != label)
+        .skip(1)
+        .take_while(|line| line.starts_with('.') || !line.ends_with(':'))
+        .collect()
+}
+
+#[test]
+fn print_float_checks_the_range_before_converting() {
+    let asm = compile("large_floats.sgm");
+    let print_float = helper(&asm, "print_float");
+
+    // 3000000000.0 arriva intero come bit pattern
+    assert!(asm.contains("li a0, 1328730206"), "3000000000.0 not loaded as 0x4f32d05e");
+
+    // il confronto con 2^31 viene prima di fcvt.w.s, che sopra satura
+    let range_check = print_float.iter().position(|line| line.starts_with("li   t0, 0x4f000000"));
+    let conversion = print_float.iter().position(|line| line.starts_with("fcvt.w.s t0"));
+    match (range_check, conversion) {
+        (Some(check), Some(conversion)) => assert!(check < conversion, "2^31 check after the conversion"),
+        _ => panic!("print_float without 2^31 check or conversion:\n{}", print_float.join("\n")),
+    }
+    assert!(print_float.contains(&".Lfloat_exponent:"), "print_float without exponent form");
+}
+
+// Un immediato decimale o esadecimale (0x300), come lo scrive il codegen
+fn immediate(text: &str) -> Option<i64> {
+    match text.strip_prefix("0x") {
+        Some(hex) => i64::from_str_radix(hex, 16).ok(),
+        None => text.parse().ok()
+    }
+}
+
+// Gli immediati che l'assembler accetta: 12 bit con segno per addi e per gli offset di
+// load e store, 0..31 per gli shift
+fn assert_immediates_fit(name: &str, asm: &str) {
+    for line in asm.lines() {
+        let code = line.split('#').next().unwrap().trim();
+        let Some((mnemonic, operands)) = code.split_once(char::is_whitespace) else { continue };
+        let operands: Vec<&str> = operands.split(',').map(str::trim).collect();
+        let last = operands.last().unwrap();
+
+        let (value, range) = match mnemonic {
+            "addi" | "andi" | "ori" | "xori" | "slti" | "sltiu" => (immediate(last), -2048..=2047),
+            "lw" | "lh" | "lhu" | "lb" | "lbu" | "sw" | "sh" | "sb" | "flw" | "fsw" =>
+                (last.split('(').next().and_then(immediate), -2048..=2047),
+            "slli" | "srli" | "srai" => (immediate(last), 0..=31),
+            _ => continue
+        };
+        let value = value.unwrap_or_else(|| panic!("'{}' in {} has no numeric immediate", code, name));
+        assert!(range.contains(&value), "'{}' in {}: immediate {} outside {:?}", code, name, value, range);
+    }
+}
+
+// Assembla con llvm-mc quando c'è (in CI di solito no: allora bastano i controlli sopra)
+fn assemble(name: &str, asm: &str) {
+    let llvm_mc = Command::new("llvm-mc").arg("--version").output();
+    if !llvm_mc.map(|result| result.status.success()).unwrap_or(false) {
+        return;
+    }
+
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR"));
+    let source = dir.join(name).with_extension("mc.s");
+    let object = dir.join(name).with_extension("o");
+    std::fs::write(&source, asm).unwrap();
+    let result = Command::new("llvm-mc")
+        .args(["-triple=riscv32", "-mattr=+m,+f", "-filetype=obj"])
+        .arg(&source)
+        .arg("-o")
+        .arg(&object)
+        .output()
+        .expect("failed to run llvm-mc");
+    assert!(result.status.success(), "{} does not assemble:\n{}", name, String::from_utf8_lossy(&result.stderr));
+}
+
+#[test]
+fn every_fixture_assembles() {
+    let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests").join("fixtures");
+    let mut fixtures: Vec<String> = std::fs::read_dir(&dir).unwrap()
+        .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+        .filter(|name| name.ends_with(".sgm"))
+        .collect();
+    fixtures.sort();
+    assert!(!fixtures.is_empty(), "no fixtures in {}", dir.display());
+
+    for name in &fixtures {
+        let asm = compile(name);
+        assert_immediates_fit(name, &asm);
+        assemble(name, &asm);
+    }
+}
+
+#[test]
+fn big_frames_and_fields_go_through_a_register() {
+    let asm = compile("big_offsets.sgm");
+    assert_frames_are_sound(&asm);
+
+    // arr[1000] da solo fa 4000 byte, ben oltre addi sp, sp, -2048
+    let functions = functions(&asm);
+    let (name, lines) = functions.iter().find(|(name, _)| name == "sum").unwrap();
+    assert!(frame_size(name, lines) > 4000, "sum frame smaller than its array");
+    assert_eq!(lines.iter().filter(|line| moves_sp(line)).count(), 2, "sp moves outside prologue/epilogue in sum");
+
+    // Big.last sta a 2400 byte dall'inizio della struct
+    let (_, lines) = functions.iter().find(|(name, _)| name == "sigma").unwrap();
+    let field = lines.iter().position(|line| line.starts_with("add  a0, a0, t6") && line.ends_with("# .last"));
+    assert!(field.is_some_and(|i| lines[i - 1] == "li   t6, 2400"), "Big.last not addressed as a0 + 2400 through t6");
+}
+
+#[test]
+fn calls_work_as_expressions_and_statements() {
+    let asm = compile("calls.sgm");
+    let sigma = function(&asm, "sigma");
+
+    // add(1, add(2, 3)) e flex add(x, 4): tre chiamate, il risultato torna in a0
+    assert_eq!(sigma.iter().filter(|line| **line == "call add").count(), 3);
+    assert_eq!(sigma.iter().filter(|line| **line == "call greet").count(), 1);
+    let print = sigma.iter().position(|line| *line == "call print_int").expect("flex add(x, 4) not printed");
+    assert_eq!(sigma[print - 1], "call add", "flex does not print the value returned by add");
+
+    let diagnostics = compile_error("calls.sgm");
+    assert_errors(&diagnostics, &[
+        "Function 'add' expects 2 arguments, but 1 were given",
+        "Function 'nope' not declared",
+        "Cannot print the result of a ghost function",
+        "Type mismatch in argument 1 of call to 'greet': expected Based, got NoCap",
+    ]);
+}
+
+#[test]
+fn nocap_values_print_as_keywords() {
+    let asm = compile("booleans.sgm");
+    let sigma = function(&asm, "sigma");
+
+    // fr, cap, un confronto e un ! passano tutti da print_bool, che sceglie tra .Lfr e .Lcap
+    assert_eq!(sigma.iter().filter(|line| **line == "call print_bool").count(), 4);
+    assert!(!sigma.contains(&"call print_int"), "a nocap printed as a number");
+    assert_eq!(section_of(&asm, ".Lfr").as_deref(), Some(".data"));
+    assert_eq!(section_of(&asm, ".Lcap").as_deref(), Some(".data"));
+    assert!(asciz_strings(&asm).contains(&"fr") && asciz_strings(&asm).contains(&"cap"));
+
+    let diagnostics = compile_error("booleans.sgm");
+    assert_errors(&diagnostics, &[
+        "Type mismatch in variable declaration 'x': expected Based, got NoCap",
+        "Type mismatch in variable declaration 'b': expected NoCap, got Based",
+        "Condition of 'ong' must be NoCap, got Based",
+        "Invalid operation Add, between 'NoCap' and 'Based': both must be numeric types!",
+    ]);
+}
+
+#[test]
+fn logical_operators_short_circuit() {
+    let asm = compile("short_circuit.sgm");
+    let sigma = function(&asm, "sigma");
+
+    // ogni && / || salta a una label dopo il lato destro: boom() e 10 / x restano fuori dal salto
+    let mut skipped = Vec::new();
+    for (i, line) in sigma.iter().enumerate() {
+        if !line.contains("# &&") && !line.contains("# ||") {
+            continue;
+        }
+        let code = line.split('#').next().unwrap().trim();
+        let target = code.strip_prefix("beqz a0, ").or_else(|| code.strip_prefix("bnez a0, "))
+            .unwrap_or_else(|| panic!("short-circuit jump '{}' is not a beqz/bnez on a0", line));
+        let label = format!("{}:", target);
+        let end = sigma.iter().position(|l| *l == label).unwrap_or_else(|| panic!("no label {}", target));
+        assert!(end > i, "'{}' jumps backwards", line);
+        skipped.push(sigma[i + 1..end].to_vec());
+    }
+    assert_eq!(skipped.len(), 3, "expected one short-circuit jump per && / ||");
+    assert!(skipped[0].contains(&"call boom") && skipped[1].contains(&"call boom"));
+    assert!(skipped[2].iter().any(|line| line.starts_with("div ")), "10 / x evaluated before x != 0");
+
+    let diagnostics = compile_error("short_circuit.sgm");
+    assert_errors(&diagnostics, &[
+        "Invalid logical operation And, between 'Based' and 'NoCap': both must be NoCap!",
+        "Invalid logical operation Or, between 'NoCap' and 'Chad': both must be NoCap!",
+        "Cannot apply '!' to 'Based': operand must be NoCap",
+    ]);
+}
+
+#[test]
+fn negative_literals_are_folded() {
+    let asm = compile("negation.sgm");
+    let sigma = function(&asm, "sigma");
+
+    // -2147483648 è un based (2147483648 da solo sarebbe superBased), -2147483649 un superBased
+    assert!(sigma.contains(&"li a0, -2147483648"), "i32::MIN not folded into a based literal");
+    assert!(sigma.contains(&"li a0, 2147483647") && sigma.contains(&"li a1, -1"), "-2147483649 not split into a0:a1");
+    assert!(sigma.contains(&"li a0, 3"), "- -3 not folded");
+    // -x e -(x - 10) restano a runtime
+    assert_eq!(sigma.iter().filter(|line| **line == "neg  a0, a0").count(), 2);
+
+    let diagnostics = compile_error("negation.sgm");
+    assert_errors(&diagnostics, &[
+        "Type mismatch in variable declaration 'x': expected Based, got SuperBased",
+        "Invalid operation Neg on 'NoCap': operand must be a numeric type!",
+        "Invalid operation Neg on 'Vibes': operand must be a numeric type!",
+    ]);
+}
+
+// Le righe di ogni flex della funzione, senza commenti: un blocco per ogni "# Print",
+// fino all'epilogo
+fn prints<'a>(lines: &[&'a str]) -> Vec<Vec<&'a str>> {
+    let mut prints: Vec<Vec<&str>> = Vec::new();
+    for line in lines {
+        if *line == "# Print" {
+            prints.push(Vec::new());
+        } else if line.starts_with(".Lreturn_") {
+            break;
+        } else if line.is_empty() || line.starts_with('#') || line.starts_with(".L") {
+            continue;
+        } else if let Some(block) = prints.last_mut() {
+            block.push(line.split('#').next().unwrap().trim());
+        }
+    }
+    prints
+}
+
+#[test]
+fn bit_operators_follow_c_precedence() {
+    let asm = compile("bit_operators.sgm");
+
+    // le operazioni di ogni flex, nell'ordine in cui vengono eseguite
+    let prints: Vec<Vec<&str>> = prints(&function(&asm, "sigma")).into_iter()
+        .map(|block| block.into_iter()
+            .filter_map(|line| line.split_whitespace().next())
+            .filter(|mnemonic| ["add", "sll", "sra", "and", "or", "xor", "rem", "mul", "not"].contains(mnemonic))
+            .collect())
+        .collect();
+
+    assert_eq!(prints, vec![
+        vec!["add", "sll"],          // (a + b) << c
+        vec!["and", "xor", "or"],    // a | (b ^ (c & 4))
+        vec!["rem", "mul"],          // (17 % c) * b
+        vec!["rem"],                 // -7 % c, con segno
+        vec!["not"],                 // ~a
+        vec!["sll", "sra"],          // >> aritmetico sui based
+    ]);
+
+    let diagnostics = compile_error("bit_operators.sgm");
+    assert_errors(&diagnostics, &[
+        "Invalid operation Mod, between 'Chill' and 'Chill': both must be integer types!",
+        "Invalid operation BitAnd, between 'NoCap' and 'Based': both must be integer types!",
+        "Invalid operation BitNot on 'Chill': operand must be an integer type!",
+    ]);
+}
+
+// Il target di un salto "j <label>  # fax (continue)"
+fn continue_targets<'a>(lines: &[&'a str]) -> Vec<&'a str> {
+    lines.iter()
+        .filter(|line| line.ends_with("# fax (continue)"))
+        .map(|line| line.split_whitespace().nth(1).unwrap())
+        .collect()
+}
+
+#[test]
+fn else_if_chains_and_continue_targets() {
+    let asm = compile("control_flow.sgm");
+
+    // tre nah ong + nah: tre condizioni, ognuna con il suo else che prova la successiva
+    let classify = function(&asm, "classify");
+    assert_eq!(classify.iter().filter(|line| line.starts_with("beqz a0, .Lelse_")).count(), 3);
+    assert_eq!(classify.iter().filter(|line| **line == "# Else body (nah)").count(), 3);
+    for branch in ["negative", "zero", "small", "big"] {
+        assert!(asciz_strings(&asm).contains(&branch), "branch \"{}\" lost", branch);
+    }
+
+    // fax nel sixSeven va all'incremento, nel mewing direttamente alla condizione
+    let sigma = function(&asm, "sigma");
+    let targets = continue_targets(&sigma);
+    assert_eq!(targets.len(), 2);
+    assert!(targets[0].starts_with(".Lfor_continue_"), "fax in sixSeven jumps to {}", targets[0]);
+    assert!(targets[1].starts_with(".Lwhile_start_"), "fax in mewing jumps to {}", targets[1]);
+
+    let label = format!("{}:", targets[0]);
+    let increment: Vec<&str> = sigma.iter()
+        .skip_while(|line| **line != label)
+        .take_while(|line| !line.starts_with("j    .Lfor_start_"))
+        .copied()
+        .collect();
+    assert!(increment.contains(&"# Increment") && increment.iter().any(|line| line.ends_with("# i =")),
+        "no increment between {} and the jump back: {:?}", targets[0], increment);
+
+    let diagnostics = compile_error("control_flow.sgm");
+    assert_errors(&diagnostics, &["'fax' outside of a loop", "'ohio' outside of a loop"]);
+}
+
+#[test]
+fn array_indices_scale_with_the_element_size() {
+    let asm = compile("arrays.sgm");
+    assert_frames_are_sound(&asm);
+    let sigma = function(&asm, "sigma");
+    let count = |wanted: &str| sigma.iter().filter(|line| line.split('#').next().unwrap().trim() == wanted).count();
+
+    // based * 4, superBased * 8, chad * 1 (nessuno shift, accesso a byte)
+    assert_eq!(count("slli a0, a0, 2"), 2, "nums[i] not scaled by 4");
+    assert_eq!(count("slli a0, a0, 3"), 2, "wide[i] not scaled by 8");
+    assert_eq!(count("sb a0, 0(t0)"), 1, "letters[1] not stored as a byte");
+    assert_eq!(count("lbu a0, 0(a0)"), 1, "letters[1] not loaded as a byte");
+    assert_eq!(count("lw a1, 4(a0)"), 1, "wide[1] not loaded as a0:a1");
+
+    let diagnostics = compile_error("arrays.sgm");
+    assert_errors(&diagnostics, &[
+        "Index 3 out of bounds for array of size 3",
+        "Array index must be Based, got Chad",
+        "Cannot assign to array 'nums' as a whole",
+        "Cannot print an array",
+        "Cannot index a value of type Based: not an array",
+    ]);
+}
+
+// Offset dei campi nell'assembly: "addi a0, a0, N   # .campo"
+fn field_offsets<'a>(lines: &[&'a str]) -> Vec<(&'a str, i32)> {
+    lines.iter()
+        .filter_map(|line| {
+            let (code, field) = line.split_once("# .")?;
+            let offset = code.trim().strip_prefix("addi a0, a0, ")?.parse().ok()?;
+            Some((field.trim(), offset))
+        })
+        .collect()
+}
+
+#[test]
+fn struct_fields_are_aligned() {
+    let asm = compile("structs.sgm");
+    assert_frames_are_sound(&asm);
+
+    // Point: tag a 0, x allineato a 4, id (superBased) a 8, 16 byte in tutto; Segment.to dopo il primo Point
+    let offsets = field_offsets(&function(&asm, "sigma"));
+    for (field, expected) in [("to", 16), ("x", 4), ("id", 8)] {
+        let found: Vec<i32> = offsets.iter().filter(|(name, _)| *name == field).map(|(_, offset)| *offset).collect();
+        assert!(!found.is_empty() && found.iter().all(|offset| *offset == expected),
+            "field .{} at {:?}, expected {}", field, found, expected);
+    }
+    // i campi a offset 0 non costano un addi
+    assert!(offsets.iter().all(|(name, _)| *name != "from" && *name != "tag"), "zero offsets emitted: {:?}", offsets);
+
+    let diagnostics = compile_error("structs.sgm");
+    assert_errors(&diagnostics, &[
+        "Field 'a' already declared in struct 'Twice'",
+        "Struct 'Loop' contains itself through field 'next'",
+        "Struct 'Point' has no field 'y'",
+        "Cannot assign to struct 'p' as a whole",
+        "Cannot print a struct",
+    ]);
+}
+
+#[test]
+fn pointers_load_store_and_scale() {
+    let asm = compile("pointers.sgm");
+    assert_frames_are_sound(&asm);
+
+    // bump legge e scrive attraverso p, non lo slot di p
+    let bump = function(&asm, "bump");
+    assert!(bump.contains(&"lw a0, 0(a0)"), "*p not loaded through the pointer");
+    assert!(bump.contains(&"sw a0, 0(t0)"), "*p slay not stored through the pointer");
+
+    let sigma = function(&asm, "sigma");
+    let call = sigma.iter().position(|line| *line == "call bump").unwrap();
+    assert!(sigma[..call].iter().any(|line| line.ends_with("# &x")), "&x not computed before bump(&x)");
+    // wide[0], w + 2 e wide[2]: sempre * 8 per i superBased
+    assert_eq!(sigma.iter().filter(|line| **line == "slli a0, a0, 3").count(), 3);
+    // pp.right e pair.right: stesso offset, attraverso il puntatore o no
+    assert_eq!(field_offsets(&sigma), vec![("right", 4), ("right", 4)]);
+
+    let diagnostics = compile_error("pointers.sgm");
+    assert_errors(&diagnostics, &[
+        "Cannot dereference 'Based': not a pointer",
+        "Cannot take the address of a temporary value",
+        "Cannot take the address of constant 'MAX'",
+        "Invalid operation Mul, between 'Pointer(Based)' and 'Based': both must be numeric types!",
+        "Cannot print a pointer",
+        "Cannot dereference 'ghosted'",
+    ]);
+}
+
+#[test]
+fn superbased_math_uses_both_halves() {
+    let asm = compile("long_math.sgm");
+    let prints = prints(&function(&asm, "sigma"));
+    assert_eq!(prints.len(), 9);
+
+    // ogni flex deve contenere l'operazione a 64 bit giusta e stampare con print_long (o print_bool)
+    let expected = [
+        ("sltu t0, a0, a2", "call print_long"),  // a + 1, riporto dalla parte bassa
+        ("sltu t0, a2, a0", "call print_long"),  // a - b, prestito
+        ("mulhu t1, a2, a0", "call print_long"), // a * b, parte alta del prodotto
+        ("call divmod_long", "call print_long"), // a / b
+        ("call divmod_long", "call print_long"), // a % b
+        ("call shl_long", "call print_long"),    // a << 4
+        ("call sra_long", "call print_long"),    // -a >> 1, aritmetico
+        ("sltu t2, a2, a0", "call print_bool"),  // a < b confronta anche le parti alte
+        ("srai a1, a0, 31", "call print_long"),  // small as superBased, estensione del segno
+    ];
+    for (block, (operation, print)) in prints.iter().zip(expected) {
+        assert!(block.contains(&operation), "missing '{}' in {:?}", operation, block);
+        let printed = block.iter().rfind(|line| line.starts_with("call print_"));
+        assert_eq!(printed, Some(&print), "wrong print helper in {:?}", block);
+    }
+    for helper in ["divmod_long:", "shl_long:", "sra_long:", "print_long:"] {
+        assert!(helper_lines(&asm).contains(&helper), "no {} helper", helper);
+    }
+
+    let diagnostics = compile_error("long_math.sgm");
+    assert_errors(&diagnostics, &[
+        "Invalid operation Add, between 'SuperBased' and 'Chill': superBased and chill cannot be mixed",
+        "Type mismatch in variable declaration 'b': expected Based, got SuperBased",
+        "Invalid operation Mod, between 'SuperBased' and 'Chill': both must be integer types!",
+    ]);
+}
+
+#[test]
+fn chill_values_travel_in_float_registers() {
+    let asm = compile("floats.sgm");
+
+    // parametri da fa0/fa1, il 2 promosso a chill, ritorno in fa0
+    let average = function(&asm, "average");
+    assert!(average.iter().any(|line| line.starts_with("fsw fa0,")) && average.iter().any(|line| line.starts_with("fsw fa1,")));
+    assert!(average.iter().any(|line| line.starts_with("fcvt.s.w ft0, a0")), "based 2 not promoted to chill");
+    assert!(average.contains(&"fmv.w.x fa0, a0"), "chill not returned in fa0");
+
+    let sigma = function(&asm, "sigma");
+    let call = sigma.iter().position(|line| *line == "call average").unwrap();
+    assert!(sigma[call - 2].starts_with("flw fa0,") && sigma[call - 1].starts_with("flw fa1,"), "arguments not in fa0/fa1");
+    assert_eq!(sigma[call + 1], "fmv.x.w a0, fa0");
+
+    let expected = [
+        ("", "call print_float"),                        // x
+        ("fmul.s ft0, ft0, ft1", "call print_float"),    // x * -2.0
+        ("flt.s a0, ft0, ft1", "call print_bool"),       // x < 2.0
+        ("fcvt.s.w ft0, a0", "call print_float"),        // 7 as chill
+        ("fcvt.w.s a0, ft0, rtz", "call print_int"),     // x as based, troncato
+        ("", "call print_float"),                        // 0.1
+    ];
+    let prints = prints(&sigma);
+    assert_eq!(prints.len(), expected.len());
+    for (block, (operation, print)) in prints.iter().zip(expected) {
+        assert!(operation.is_empty() || block.contains(&operation), "missing '{}' in {:?}", operation, block);
+        assert_eq!(block.iter().rfind(|line| line.starts_with("call print_")), Some(&print), "wrong print helper in {:?}", block);
+    }
+
+    // 6 decimali arrotondati (0.1 non diventa 0.099999) senza gli zeri in fondo (1.875, non 1.875000)
+    let print_float = helper(&asm, "print_float");
+    assert!(print_float.iter().any(|line| line.starts_with("fcvt.w.s t2, ft1, rne")), "fraction not rounded");
+    assert!(print_float.contains(&".Lfloat_trim:"), "trailing zeros not trimmed");
+
+    let diagnostics = compile_error("floats.sgm");
+    assert_errors(&diagnostics, &[
+        "Type mismatch in variable declaration 'x': expected Based, got Chill",
+        "Type mismatch in argument 1 of call to 'half': expected Chill, got NoCap",
+        "Invalid operation Mul, between 'SuperBased' and 'Chill': superBased and chill cannot be mixed",
+    ]);
+}
+
+#[test]
+fn casts_convert_between_representations() {
+    let asm = compile("casts.sgm");
+
+    let expected = [
+        ("", "call print_int"),                  // c as based: stesso valore
+        ("andi a0, a0, 255", "call print_char"), // based -> chad tiene un byte
+        ("srai a1, a0, 31", "call print_long"),  // based -> superBased con il segno
+        ("", "call print_int"),                  // superBased -> based: solo la parte bassa
+        ("fcvt.s.w ft0, a0", "call print_float"),
+        ("call int_to_str", "call print_string"),
+        ("call str_to_int", "call print_int"),
+    ];
+    let prints = prints(&function(&asm, "sigma"));
+    assert_eq!(prints.len(), expected.len());
+    for (block, (operation, print)) in prints.iter().zip(expected) {
+        assert!(operation.is_empty() || block.contains(&operation), "missing '{}' in {:?}", operation, block);
+        assert_eq!(block.iter().rfind(|line| line.starts_with("call print_")), Some(&print), "wrong print helper in {:?}", block);
+    }
+
+    // i cast tra due tipi convertibili in based suggeriscono il doppio cast, gli altri elencano quelli permessi
+    let diagnostics = compile_error("casts.sgm");
+    assert_errors(&diagnostics, &[
+        "Cannot cast 'SuperBased' to 'Chill'",
+        "Cannot cast 'Chad' to 'SuperBased'",
+        "Cannot cast 'NoCap' to 'Based'",
+        "Cannot cast 'Struct(\"Point\")' to 'Based'",
+    ]);
+    assert!(diagnostics.contains("help: cast through based first, e.g. '(x as based) as chill'"));
+    assert_eq!(diagnostics.matches("note: allowed casts:").count(), 2);
+}
+
+#[test]
+fn flex_picks_the_helper_from_the_expression_type() {
+    let asm = compile("chad_printing.sgm");
+
+    // variabile, campo array, ritorno di funzione, indice di una vibes e cast: tutti chad
+    let printed: Vec<&str> = prints(&function(&asm, "sigma")).iter()
+        .map(|block| *block.iter().rfind(|line| line.starts_with("call print_")).expect("flex without print helper"))
+        .collect();
+    assert_eq!(printed, vec![
+        "call print_char",
+        "call print_char",
+        "call print_char",
+        "call print_string",
+        "call print_char",
+        "call print_char",
+    ]);
+    // i chad si caricano come byte senza segno
+    let sigma = function(&asm, "sigma");
+    assert!(!sigma.iter().any(|line| line.starts_with("lb ")), "chad loaded with sign extension");
+
+    let diagnostics = compile_error("chad_printing.sgm");
+    assert_errors(&diagnostics, &["Cannot print an array", "Cannot print the result of a ghost function"]);
+}
+
+#[test]
+fn every_use_resolves_to_its_declaration() {
+    let asm = compile("shadowing.sgm");
+    assert_frames_are_sound(&asm);
+
+    // per ogni flex: da dove viene letto n e con quale helper si stampa
+    let loads: Vec<(&str, &str)> = prints(&function(&asm, "sigma")).iter()
+        .map(|block| {
+            let load = block.iter().find(|line| line.starts_with("la ") || line.starts_with("lw ") || line.starts_with("lbu ") || line.starts_with("call "));
+            let print = block.iter().rfind(|line| line.starts_with("call print_"));
+            (*load.unwrap(), *print.unwrap())
+        })
+        .collect();
+    assert_eq!(loads, vec![
+        ("la a0, .Lglobal_n", "call print_int"),   // prima della locale: la globale
+        ("lw a0, 0(s0)", "call print_int"),        // based n locale
+        ("lbu a0, 4(s0)", "call print_char"),      // chad n del blocco, nel suo slot
+        ("lw a0, 0(s0)", "call print_int"),        // fuori dal blocco torna il based
+        ("lw a0, 0(s0)", "call print_int"),        // param(n) riceve il based locale
+    ]);
+    // dentro param, n è il parametro
+    let param = function(&asm, "param");
+    assert!(!param.iter().any(|line| line.contains(".Lglobal_n")), "parameter n resolved to the global");
+
+    let diagnostics = compile_error("shadowing.sgm");
+    assert_errors(&diagnostics, &[
+        "Variable 'inner' not declared",
+        "Variable 'x' already declared in this scope",
+        "Variable 'missing' not declared",
+    ]);
+}
+
+#[test]
+fn input_builtins_call_the_read_helpers() {
+    let asm = compile("input.sgm");
+    let sigma = function(&asm, "sigma");
+
+    let calls: Vec<&str> = sigma.iter().filter(|line| line.starts_with("call read_")).copied().collect();
+    assert_eq!(calls, vec!["call read_int", "call read_char", "call read_line"]);
+
+    // readVibes(buffer, 16): l'indirizzo del buffer (non il suo contenuto) in a0 e la dimensione in a1
+    let read_line = sigma.iter().position(|line| *line == "call read_line").unwrap();
+    let declaration = sigma.iter().position(|line| *line == "# VarDecl: vibes line").unwrap();
+    assert!(sigma[declaration..read_line].iter().any(|line| line.ends_with("# indirizzo di buffer")), "buffer passed by value");
+    assert!(sigma[read_line - 2].starts_with("lw a0,") && sigma[read_line - 1].starts_with("lw a1,"), "arguments not in a0/a1");
+
+    // tutti leggono con la syscall read (63) da stdin
+    for name in ["read_int", "read_char", "read_line"] {
+        let helper = helper(&asm, name);
+        assert!(helper.iter().any(|line| line.starts_with("li   a7, 63")), "{} does not use the read syscall", name);
+    }
+
+    let diagnostics = compile_error("input.sgm");
+    assert_errors(&diagnostics, &[
+        "Builtin 'readBased' expects 0 arguments, but 1 were given",
+        "Size 20 is larger than the buffer of 10 chad",
+        "Type mismatch in argument 1 of call to 'readVibes': expected Array(Chad, None), got Array(Based, Some(10))",
+        "Type mismatch in variable declaration 'c': expected Chad, got Based",
+    ]);
+}
+
+#[test]
+fn heap_allocates_first_fit_from_the_free_list() {
+    let asm = compile("heap.sgm");
+    let sigma = function(&asm, "sigma");
+    assert_eq!(sigma.iter().filter(|line| **line == "call heap_alloc").count(), 2);
+    assert_eq!(sigma.iter().filter(|line| **line == "call heap_free").count(), 3);
+
+    // heap_alloc prima scorre la free list e prende il primo blocco abbastanza grande,
+    // solo se non lo trova allarga lo heap con brk
+    let alloc = helper(&asm, "heap_alloc");
+    let position = |wanted: &str| alloc.iter().position(|line| line.starts_with(wanted)).unwrap_or_else(|| panic!("no '{}' in heap_alloc", wanted));
+    assert!(position("la   t1, .Lfree_list") < position(".Lalloc_search:"));
+    assert!(position("bgeu t3, t0, .Lalloc_found") < position(".Lalloc_grow:"), "the free list is not searched first");
+    assert!(position(".Lalloc_grow:") < position("li   a7, 214"), "brk before searching the free list");
+
+    // free rimette il blocco in testa alla lista, e free(ghosted) non fa niente
+    let free = helper(&asm, "heap_free");
+    assert!(free.iter().any(|line| line.starts_with("beqz a0, .Lfree_done")));
+    assert!(free.iter().any(|line| line.starts_with("la   t1, .Lfree_list")));
+    assert_eq!(section_of(&asm, ".Lfree_list").as_deref(), Some(".data"));
+
+    let diagnostics = compile_error("heap.sgm");
+    assert_errors(&diagnostics, &[
+        "Type mismatch in variable declaration 'x': expected Based, got Pointer(Ghost)",
+        "Type mismatch in argument 1 of call to 'alloc': expected Based, got Chad",
+        "Type mismatch in argument 1 of call to 'free': expected Pointer(Ghost), got Based",
+    ]);
+}
+
+#[test]
+fn string_operations_call_the_string_helpers() {
+    let asm = compile("strings.sgm");
+
+    // ogni flex: le chiamate e i confronti che fa, nell'ordine
+    let prints: Vec<Vec<&str>> = prints(&function(&asm, "sigma")).into_iter()
+        .map(|block| block.into_iter()
+            .filter(|line| line.starts_with("call ") || line.starts_with("seqz") || line.starts_with("sltz") || line.starts_with("lbu"))
+            .collect())
+        .collect();
+    assert_eq!(prints, vec![
+        vec!["call print_string"],                                                         // both, già concatenata
+        vec!["call str_len", "call print_int"],
+        vec!["call str_compare", "seqz a0, a0", "call print_bool"],                       // contenuto, non indirizzi
+        vec!["call str_compare", "sltz a0, a0", "call print_bool"],                       // ordine alfabetico
+        vec!["lbu a0, 0(a0)", "call print_char"],                                         // both[8] è un chad
+        vec!["call str_len", "call int_to_str", "call str_concat", "call print_string"],
+    ]);
+    let sigma = function(&asm, "sigma");
+    let declaration = sigma.iter().position(|line| *line == "# VarDecl: vibes both").unwrap();
+    assert!(sigma[declaration..].iter().take_while(|line| **line != "# Print").any(|line| *line == "call str_concat"), "a + b not concatenated");
+
+    // la stringa nuova sta sullo heap
+    assert!(helper(&asm, "str_concat").contains(&"call heap_alloc"), "str_concat does not allocate");
+
+    let diagnostics = compile_error("strings.sgm");
+    assert_errors(&diagnostics, &[
+        "Cannot concatenate 'Vibes' and 'Based': both must be Vibes",
+        "Cannot assign to a character of a vibes",
+        "Cannot compare 'Vibes' and 'Chad': types must match",
+        "Type mismatch in argument 1 of call to 'len': expected Vibes, got Based",
+    ]);
+}